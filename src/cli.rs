@@ -0,0 +1,53 @@
+
+use std::env;
+
+/// What the launcher is asked to do on the command line.
+pub enum CliCommand {
+    /// Open the terminal interface.
+    Interactive,
+    /// Print the resolved command of a game without running anything.
+    DryRun { selector: String },
+    /// Launch a game and wait for it to exit, as desktop entries do.
+    Run { selector: String },
+    /// Write the desktop entries of a game, or of every game of a tab.
+    DesktopAdd { selector: String },
+    /// Remove the desktop entries written by `DesktopAdd`, of a game or a tab, or all of them.
+    DesktopRemove { selector: Option<String> },
+    /// Add the games started by existing desktop entries to the library.
+    DesktopImport { dirs: Vec<String> },
+}
+
+impl CliCommand {
+
+    pub fn from_env() -> Result<CliCommand, failure::Error> {
+
+        let mut args = env::args().skip(1);
+
+        match args.next() {
+            | None => Ok(CliCommand::Interactive),
+            | Some(ref flag) if flag == "--dry-run" => {
+                let selector = args.next()
+                    .ok_or_else(|| failure::err_msg("--dry-run requires a game, as `<game>` or `<tab>/<game>`."))?;
+                Ok(CliCommand::DryRun { selector })
+            },
+            | Some(ref command) if command == "run" => {
+                let selector = args.next()
+                    .ok_or_else(|| failure::err_msg("run requires a game, as `<game>` or `<tab>/<game>`."))?;
+                Ok(CliCommand::Run { selector })
+            },
+            | Some(ref command) if command == "desktop" => {
+                match args.next().as_deref() {
+                    | Some("add") => {
+                        let selector = args.next()
+                            .ok_or_else(|| failure::err_msg("desktop add requires a game or a tab, as `<tab>/<game>` or `<tab>/`."))?;
+                        Ok(CliCommand::DesktopAdd { selector })
+                    },
+                    | Some("remove") => Ok(CliCommand::DesktopRemove { selector: args.next() }),
+                    | Some("import") => Ok(CliCommand::DesktopImport { dirs: args.collect() }),
+                    | _ => Err(failure::err_msg("desktop requires `add <game>`, `remove [<game>]` or `import [<dir>...]`.")),
+                }
+            },
+            | Some(unknown) => Err(failure::err_msg(format!("Unknown argument: {}", unknown))),
+        }
+    }
+}
@@ -0,0 +1,13 @@
+
+/// What to do when a `pre_launch` or `post_launch` command exits with failure.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HookPolicy {
+    /// Stop the launch (only meaningful for `pre_launch` commands).
+    Abort,
+    /// Keep going, but show the failure in the instruction panel.
+    #[default]
+    Warn,
+    /// Keep going silently. The failure is still written to the session log.
+    Ignore,
+}
@@ -2,6 +2,10 @@
 use termion::event::Key;
 
 pub const MANIFEST_CONFIG_NAME: &str = "thl.toml";
+pub const SESSION_LOG_NAME: &str = "thl-session.log";
+pub const PREFIX_DIR_NAME: &str = "thl-prefixes";
+pub const VERSIONS_FILE_NAME: &str = "thl-versions.txt";
+pub const LIBRARY_DIR_NAME: &str = "thl-library";
 pub const EXIT_KEY: Key = Key::Esc;
 
 pub const HIGHLIGHT_SYMBOL: &str = "🏹";
@@ -2,13 +2,15 @@
 pub mod manifest;
 pub mod tab;
 pub mod setting;
+pub mod hook;
 
 use crate::config::tab::{ TabsConfig, TabConfig, ItemConfig };
 use crate::config::setting::SettingConfig;
 use crate::config::manifest::MANIFEST_CONFIG_NAME;
+use crate::library::titles;
 
 use std::env;
-use std::path::PathBuf;
+use std::path::{ Path, PathBuf };
 use std::fs;
 use std::io::{ Read, Write };
 
@@ -39,28 +41,31 @@ impl ConfigAbstract for EngineConfig {
 
 impl EngineConfig {
 
-    pub fn init() -> Option<EngineConfig> {
+    /// Read the manifest of `dir` or of its closest parent that has one.
+    pub fn init(dir: &Path) -> Option<EngineConfig> {
 
-        EngineConfig::search_manifest()
+        EngineConfig::search_manifest(dir)
             .and_then(EngineConfig::read_manifest)
             .and_then(|content| toml::from_str(&content).ok())
     }
 
     pub fn write_manifest(&self) -> Result<(), failure::Error> {
+        self.write_manifest_in(&env::current_dir()?)
+    }
+
+    pub fn write_manifest_in(&self, dir: &Path) -> Result<(), failure::Error> {
 
         let content = toml::to_string_pretty(self)?;
-        let cwd = env::current_dir()?;
 
-        let mut file = fs::File::create(cwd.join(MANIFEST_CONFIG_NAME))?;
+        let mut file = fs::File::create(dir.join(MANIFEST_CONFIG_NAME))?;
         let _ = file.write(content.as_bytes())?;
 
         Ok(())
     }
 
-    fn search_manifest() -> Option<PathBuf> {
+    fn search_manifest(dir: &Path) -> Option<PathBuf> {
 
-        let cwd = env::current_dir().ok()?;
-        let mut current = cwd.as_path();
+        let mut current = dir;
 
         loop {
 
@@ -92,8 +97,25 @@ impl EngineConfig {
 
     pub fn update(&mut self, op: ConfigOp) -> crate::THLError {
 
+        if let ConfigOp::None = op {
+            return Ok(())
+        }
+
+        self.apply(op);
+
+        // update local toml file.
+        self.write_manifest()
+    }
+
+    fn apply(&mut self, op: ConfigOp) {
+
         match op {
-            | ConfigOp::None => return Ok(()),
+            | ConfigOp::None => {},
+            | ConfigOp::Batch(ops) => {
+                for op in ops.into_iter() {
+                    self.apply(op);
+                }
+            },
             | ConfigOp::AppendTab { config } => {
                 self.tabs.tabs.push(config);
             },
@@ -101,23 +123,35 @@ impl EngineConfig {
                 self.tabs.tabs.remove(tab_index);
             },
             | ConfigOp::AppendGame { tab_index, config } => {
-                self.tabs.tabs[tab_index].items.push(config);
+                self.tabs.tabs[tab_index].items.push(*config);
             },
             | ConfigOp::RemoveGame { tab_index, item_index } => {
                 self.tabs.tabs[tab_index].items.remove(item_index);
             },
+            | ConfigOp::SetVariant { tab_index, item_index, variant } => {
+                self.tabs.tabs[tab_index].items[item_index].variant = variant;
+            },
+            | ConfigOp::SetPath { tab_index, item_index, path } => {
+                self.tabs.tabs[tab_index].items[item_index].path = path;
+            },
+            | ConfigOp::SortGames { tab_index } => {
+                titles::sort_items(&mut self.tabs.tabs[tab_index].items);
+            },
         }
-
-        // update local toml file.
-        self.write_manifest()
     }
 }
 
 pub enum ConfigOp {
 
     None,
+    /// Several operations applied in order, such as the games accepted from a library scan.
+    Batch(Vec<ConfigOp>),
     AppendTab { config: TabConfig },
     RemoveTab { tab_index: usize },
-    AppendGame { tab_index: usize, config: ItemConfig },
+    AppendGame { tab_index: usize, config: Box<ItemConfig> },
     RemoveGame { tab_index: usize, item_index: usize },
+    SetVariant { tab_index: usize, item_index: usize, variant: Option<String> },
+    SetPath { tab_index: usize, item_index: usize, path: String },
+    /// Sort the games of a tab in release order.
+    SortGames { tab_index: usize },
 }
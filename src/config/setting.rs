@@ -1,13 +1,64 @@
 
+use std::collections::BTreeMap;
 use std::time::Duration;
 
 use crate::config::ConfigAbstract;
+use crate::config::hook::HookPolicy;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SettingConfig {
 
     pub is_close_after_game_launch: bool,
+    /// Commands run before every game, ahead of the tab and item ones.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_launch: Vec<String>,
+    /// Commands run after every game has exited.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_launch: Vec<String>,
+    #[serde(default)]
+    pub hook_policy: HookPolicy,
+    /// The program used to start every game, such as `wine`. Empty to execute games directly.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runner: Option<String>,
+    /// Commands prefixed to every game, outermost first, such as `taskset -c 0` or `gamemoderun`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub wrappers: Vec<String>,
+    /// Allow starting a game again while a previous copy is still running.
+    #[serde(default)]
+    pub allow_multiple_instances: bool,
+    /// Directory of the lock files shared by every launcher instance. Empty to only guard this instance.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance_lock_dir: Option<String>,
+    /// Directory of the Wine prefixes managed by the launcher. Defaults to `thl-prefixes` next to the manifest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix_dir: Option<String>,
+    /// Directory the archives are installed into. Defaults to `thl-library` next to the manifest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub library_dir: Option<String>,
+    /// Directories searched for installed games by the library scan.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scan_roots: Vec<String>,
+    /// The Steam installation whose games the library scan also imports.
+    /// Defaults to `~/.local/share/Steam` or `~/.steam/steam`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub steam_root: Option<String>,
+    /// The Wine prefixes whose `drive_c` is searched for installed games.
+    /// Defaults to `~/.wine` and the managed prefixes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub wine_prefixes: Vec<String>,
+    /// The directory of the Lutris game configs to import from.
+    /// Defaults to `~/.local/share/lutris/games` or `~/.config/lutris/games`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lutris_dir: Option<String>,
     pub tick_rate: Duration,
+    /// Windows path prefixes of a shared manifest and their local counterparts,
+    /// such as `'D:\Games' = '/mnt/games'`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub path_map: BTreeMap<String, String>,
+    /// Steam app ids and the game ids they install, such as `1234560 = 'th17'`. An app listed here
+    /// is recognized by its id first, before the executables and the name in its manifest.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub steam_app_ids: BTreeMap<String, String>,
 }
 
 impl Default for SettingConfig {
@@ -16,7 +67,22 @@ impl Default for SettingConfig {
 
         SettingConfig {
             is_close_after_game_launch: false,
+            pre_launch : vec![],
+            post_launch: vec![],
+            hook_policy: HookPolicy::default(),
+            runner     : None,
+            wrappers   : vec![],
+            allow_multiple_instances: false,
+            instance_lock_dir: None,
+            prefix_dir: None,
+            library_dir: None,
+            scan_roots: vec![],
+            steam_root: None,
+            wine_prefixes: vec![],
+            lutris_dir: None,
             tick_rate: Duration::from_millis(250),
+            path_map: BTreeMap::new(),
+            steam_app_ids: BTreeMap::new(),
         }
     }
 }
@@ -1,5 +1,8 @@
 
+use std::collections::BTreeMap;
+
 use crate::config::ConfigAbstract;
+use crate::config::hook::HookPolicy;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TabsConfig {
@@ -11,6 +14,28 @@ pub struct TabsConfig {
 pub struct TabConfig {
 
     pub name: String,
+    /// Overrides the global `runner` for the games in this tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runner: Option<String>,
+    /// Appended to the global `wrappers` for the games in this tab.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub wrappers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_launch: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_launch: Vec<String>,
+    /// Overrides the global `hook_policy` for the games in this tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hook_policy: Option<HookPolicy>,
+    /// Locale of the games in this tab, such as `ja_JP.UTF-8`. Sets `LANG` and `LC_ALL`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// Name of the managed Wine prefix shared by the games in this tab.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wine_prefix: Option<String>,
+    /// Environment variables set for every game in this tab.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
     pub items: Vec<ItemConfig>,
 }
 
@@ -19,6 +44,115 @@ pub struct ItemConfig {
 
     pub name: String,
     pub path: String,
+    /// The program used to start `path`, such as `wine`. Overrides the tab and global `runner`,
+    /// an empty string executes `path` directly.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runner: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Working directory of the game. Defaults to the directory containing `path`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// Name of the last used entry of `variants`, applied on launch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+    /// Appended to the global and tab `wrappers`, so the item ones are the innermost.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub wrappers: Vec<String>,
+    /// Set to `false` to ignore the global and tab `wrappers`. Defaults to `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inherit_wrappers: Option<bool>,
+    /// The CPUs the game may run on. Older titles slow down when spread over several cores.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cpu_affinity: Vec<usize>,
+    /// Scheduling priority of the game, from -20 (highest) to 19 (lowest).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nice: Option<i32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_launch: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_launch: Vec<String>,
+    /// Overrides the tab and global `hook_policy` for this game.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hook_policy: Option<HookPolicy>,
+    /// Set to `false` to ignore the global and tab hooks. Defaults to `true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inherit_hooks: Option<bool>,
+    /// Overrides the global `allow_multiple_instances` for this game.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow_multiple_instances: Option<bool>,
+    /// Seconds the game may run before it is terminated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_runtime: Option<u64>,
+    /// Seconds without any CPU time used after which the game is considered hung and restarted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_restart: Option<u64>,
+    /// Restart the game when it crashes or exits with an error. Defaults to `false`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart_on_crash: Option<bool>,
+    /// Seconds to wait before the first restart, doubled after each attempt. Defaults to 5.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart_backoff: Option<u64>,
+    /// Restarts allowed in a row before giving up. Defaults to 3.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_restarts: Option<u32>,
+    /// Seconds a game must run for its earlier restarts to be forgotten. Defaults to 600.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart_reset_after: Option<u64>,
+    /// Overrides the tab `locale`, an empty string keeps the locale of the launcher.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    /// Name of the managed Wine prefix of this game, created on its first launch.
+    /// Overrides the tab `wine_prefix`, an empty string keeps the `WINEPREFIX` of the launcher.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wine_prefix: Option<String>,
+    /// Resource limits of the game, such as `nofile = 4096`. See `setrlimit(2)` for the names.
+    /// Only the soft limit is set, the hard one is kept unless given in `hard_limits`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub limits: BTreeMap<String, u64>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub hard_limits: BTreeMap<String, u64>,
+    /// Environment variables set for this game, on top of the tab ones.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Start the game through thcrap with a patch stack, instead of running `path` directly.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thcrap: Option<ThcrapConfig>,
+    /// Companion tools of the game, such as `custom.exe` or the manual.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<ActionConfig>,
+    /// Alternative setups of the game, such as a patched or windowed one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<VariantConfig>,
+}
+
+/// A secondary command of a game, picked from its actions menu.
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct ActionConfig {
+
+    pub name: String,
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Defaults to the directory containing `path`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// Defaults to the runner of the game. Set to an empty string to execute `path` directly,
+    /// such as `xdg-open` for a manual or a folder.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runner: Option<String>,
+}
+
+/// The thcrap setup of a game, launched as `thcrap_loader.exe <config>.js <game>`.
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct ThcrapConfig {
+
+    /// The thcrap installation directory.
+    pub dir: String,
+    /// Name of the run configuration, such as `en` for `config/en.js`.
+    pub config: String,
+    /// The game id known to thcrap, such as `th08`.
+    pub game: String,
 }
 
 impl Default for TabsConfig {
@@ -28,6 +162,7 @@ impl Default for TabsConfig {
         let welcome_tab = TabConfig {
             name: String::from("Default"),
             items: vec![],
+            ..TabConfig::default()
         };
 
         TabsConfig {
@@ -36,6 +171,123 @@ impl Default for TabsConfig {
     }
 }
 
+impl TabsConfig {
+
+    /// Find a game by `tab/game` or by game name alone, ignoring case unless that makes it ambiguous.
+    /// A `#2` suffix picks the second game of that name, as written by `desktop::entry_id`.
+    pub fn find_item(&self, selector: &str) -> Option<(&TabConfig, &ItemConfig)> {
+
+        let (selector, rank) = match selector.rsplit_once('#') {
+            | Some((name, rank)) if !rank.is_empty() && rank.chars().all(|ch| ch.is_ascii_digit()) => {
+                (name, rank.parse::<usize>().ok()?.max(1))
+            },
+            | _ => (selector, 1),
+        };
+
+        let (tab_name, item_name) = match selector.find('/') {
+            | Some(split) => (Some(&selector[..split]), &selector[split + 1..]),
+            | None => (None, selector),
+        };
+
+        let matching = |same: fn(&str, &str) -> bool| -> Vec<(&TabConfig, &ItemConfig)> {
+            self.tabs.iter()
+                .filter(|tab| tab_name.is_none_or(|name| same(&tab.name, name)))
+                .flat_map(|tab| tab.items.iter().map(move |item| (tab, item)))
+                .filter(|(_, item)| same(&item.name, item_name))
+                .collect()
+        };
+
+        let mut found = matching(|a, b| a == b);
+        if found.is_empty() {
+            found = matching(|a, b| a.eq_ignore_ascii_case(b));
+        }
+        found.get(rank - 1).cloned()
+    }
+
+    /// Find a tab by name, ignoring case and a trailing `/`.
+    pub fn find_tab(&self, selector: &str) -> Option<&TabConfig> {
+
+        let name = selector.strip_suffix('/').unwrap_or(selector);
+        self.tabs.iter().find(|tab| tab.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// A named setup of a game. Every field that is set overrides the one of the game.
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct VariantConfig {
+
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runner: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub args: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wrappers: Option<Vec<String>>,
+    /// Merged over the environment of the game.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thcrap: Option<ThcrapConfig>,
+}
+
+impl ItemConfig {
+
+    /// The game as it is launched, with its selected variant applied.
+    pub fn resolve_variant(&self) -> ItemConfig {
+
+        let mut item = self.clone();
+
+        let variant = self.variant.as_ref()
+            .and_then(|name| self.variants.iter().find(|variant| &variant.name == name));
+
+        if let Some(variant) = variant {
+            if let Some(ref path) = variant.path {
+                item.path = path.clone();
+            }
+            if let Some(ref runner) = variant.runner {
+                item.runner = Some(runner.clone());
+            }
+            if let Some(ref args) = variant.args {
+                item.args = args.clone();
+            }
+            if let Some(ref wrappers) = variant.wrappers {
+                item.wrappers = wrappers.clone();
+            }
+            item.env.extend(variant.env.iter().map(|(k, v)| (k.clone(), v.clone())));
+            if let Some(ref thcrap) = variant.thcrap {
+                item.thcrap = Some(thcrap.clone());
+            }
+        }
+
+        item
+    }
+
+    /// Turn the action at `index` into a standalone item, keeping the runner, locale, Wine prefix and
+    /// environment of this game but none of its hooks, wrappers or watchdog settings.
+    pub fn action_item(&self, index: usize) -> Option<ItemConfig> {
+
+        let action = self.actions.get(index)?;
+
+        let item = ItemConfig {
+            name: format!("{}: {}", self.name, action.name),
+            path: action.path.clone(),
+            runner: action.runner.clone().or_else(|| self.runner.clone()),
+            args: action.args.clone(),
+            cwd : action.cwd.clone(),
+            inherit_wrappers: Some(false),
+            inherit_hooks: Some(false),
+            locale: self.locale.clone(),
+            wine_prefix: self.wine_prefix.clone(),
+            env: self.env.clone(),
+            ..ItemConfig::default()
+        };
+
+        Some(item)
+    }
+}
+
 impl ConfigAbstract for TabsConfig {
 
     fn parse_toml(toml: &toml::Value) -> Option<TabsConfig> {
@@ -71,7 +323,8 @@ impl ConfigAbstract for TabConfig {
             }).collect();
 
             let config = TabConfig {
-                name, items
+                name, items,
+                ..TabConfig::default()
             };
 
             Some(config)
@@ -92,7 +345,78 @@ impl ConfigAbstract for ItemConfig {
 
         let config = ItemConfig {
             name, path,
+            ..ItemConfig::default()
         };
         Some(config)
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn tabs(names: &[(&str, &[&str])]) -> TabsConfig {
+
+        let tabs = names.iter()
+            .map(|(tab, items)| TabConfig {
+                name: tab.to_string(),
+                items: items.iter().map(|item| ItemConfig { name: item.to_string(), ..ItemConfig::default() }).collect(),
+                ..TabConfig::default()
+            })
+            .collect();
+        TabsConfig { tabs }
+    }
+
+    #[test]
+    fn finds_items_by_selector() {
+
+        let tabs = tabs(&[("Mainline", &["th08", "th08", "th07"]), ("MAINLINE", &["th08"])]);
+        let find = |selector: &str| {
+            tabs.find_item(selector).map(|(tab, item)| {
+                let index = tab.items.iter().position(|other| std::ptr::eq(other, item)).unwrap();
+                (tab.name.as_str(), index)
+            })
+        };
+
+        assert_eq!(find("Mainline/th08"), Some(("Mainline", 0)));
+        assert_eq!(find("Mainline/th08#2"), Some(("Mainline", 1)));
+        assert_eq!(find("Mainline/th08#3"), None);
+        // an exact match wins over one ignoring case.
+        assert_eq!(find("MAINLINE/th08"), Some(("MAINLINE", 0)));
+        assert_eq!(find("mainline/TH07"), Some(("Mainline", 2)));
+        assert_eq!(find("th07"), Some(("Mainline", 2)));
+        assert_eq!(find("Extra/th08"), None);
+    }
+
+    #[test]
+    fn action_item_runs_in_the_game_environment() {
+
+        let game = ItemConfig {
+            name: String::from("th08"),
+            path: String::from("/games/th08/th08.exe"),
+            runner: Some(String::from("wine")),
+            pre_launch: vec![String::from("mount-iso")],
+            locale: Some(String::from("ja_JP.UTF-8")),
+            wine_prefix: Some(String::from("th08")),
+            env: vec![(String::from("WINEDEBUG"), String::from("-all"))].into_iter().collect(),
+            actions: vec![ActionConfig {
+                name: String::from("Config"),
+                path: String::from("/games/th08/custom.exe"),
+                ..ActionConfig::default()
+            }],
+            ..ItemConfig::default()
+        };
+
+        let action = game.action_item(0).unwrap();
+
+        assert_eq!(action.name, "th08: Config");
+        assert_eq!(action.runner, game.runner);
+        assert_eq!(action.locale, game.locale);
+        assert_eq!(action.wine_prefix, game.wine_prefix);
+        assert_eq!(action.env, game.env);
+        assert!(action.pre_launch.is_empty());
+        assert_eq!(action.inherit_hooks, Some(false));
+        assert!(game.action_item(1).is_none());
+    }
+}
@@ -0,0 +1,317 @@
+
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{ Path, PathBuf };
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+
+use crate::config::setting::SettingConfig;
+use crate::config::tab::{ TabConfig, ItemConfig };
+use crate::launch::locale;
+use crate::launch::prefix::WinePrefix;
+use crate::launch::tuning::ProcessTuning;
+use crate::launch::winpath::{ self, PathMap };
+use crate::thcrap;
+
+/// The fully resolved command of a game, after runner, arguments and environment are composed.
+#[derive(Debug, Clone)]
+pub struct LaunchCommand {
+
+    pub program: String,
+    pub args: Vec<String>,
+    pub cwd: PathBuf,
+    /// Variables set on top of the environment of the launcher.
+    pub env: BTreeMap<String, String>,
+    pub tuning: ProcessTuning,
+    /// Problems that prevent the command from being run, such as a broken thcrap setup.
+    pub errors: Vec<String>,
+    /// Problems the game can start with, but that are likely to show in it.
+    pub warnings: Vec<String>,
+}
+
+impl LaunchCommand {
+
+    pub fn compose(setting: &SettingConfig, tab: &TabConfig, item: &ItemConfig) -> LaunchCommand {
+
+        let item = &PathMap::from_setting(setting).localize(item);
+        let runner = resolve_runner(setting, tab, item);
+
+        let inherited: Vec<&String> = if item.inherit_wrappers.unwrap_or(true) {
+            setting.wrappers.iter().chain(tab.wrappers.iter()).collect()
+        } else {
+            vec![]
+        };
+
+        // wrappers come first, then the runner, then the game itself.
+        let mut words: Vec<String> = inherited.into_iter()
+            .chain(item.wrappers.iter())
+            .flat_map(|wrapper| split_words(wrapper))
+            .collect();
+        words.extend(runner.cloned());
+
+        let mut errors = vec![];
+        let mut default_cwd = Path::new(&item.path).parent().map(Path::to_path_buf);
+
+        match item.thcrap {
+            | Some(ref thcrap) => match thcrap::loader_command(thcrap) {
+                | Ok((loader, loader_args)) => {
+                    words.push(loader.to_string_lossy().into_owned());
+                    words.extend(loader_args);
+                    default_cwd = Some(PathBuf::from(&thcrap.dir));
+                },
+                | Err(e) => {
+                    errors.push(e);
+                    words.push(item.path.clone());
+                },
+            },
+            | None => words.push(item.path.clone()),
+        }
+
+        // Windows programs only understand local paths in their arguments through the `Z:` drive.
+        let is_windows_program = runner.is_some()
+            && (item.thcrap.is_some() || item.path.to_ascii_lowercase().ends_with(".exe"));
+        words.extend(item.args.iter().map(|arg| {
+            if is_windows_program { winpath::to_wine(arg).unwrap_or_else(|| arg.clone()) } else { arg.clone() }
+        }));
+
+        let program = words.remove(0);
+        let args = words;
+
+        let cwd = match item.cwd {
+            | Some(ref cwd) => PathBuf::from(cwd),
+            | None => default_cwd
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .unwrap_or_else(|| env::current_dir().unwrap_or_default()),
+        };
+
+        let mut warnings = vec![];
+        let mut vars = BTreeMap::new();
+
+        let locale = item.locale.as_ref()
+            .or(tab.locale.as_ref())
+            .filter(|locale| !locale.is_empty());
+        if let Some(locale) = locale {
+            // the runner picks the code page of the game from the locale, Shift-JIS for `ja_JP`.
+            vars.insert(String::from("LANG"), locale.clone());
+            vars.insert(String::from("LC_ALL"), locale.clone());
+
+            if locale::is_installed(locale) == Some(false) {
+                warnings.push(format!("locale `{}` is not installed, texts may be garbled (see `locale -a`)", locale));
+            }
+        }
+
+        match WinePrefix::for_item(setting, tab, item) {
+            | Some(Ok(prefix)) => {
+                vars.insert(String::from("WINEPREFIX"), prefix.path.to_string_lossy().into_owned());
+            },
+            | Some(Err(e)) => errors.push(e),
+            | None => {},
+        }
+
+        // explicit variables win over the ones derived from `locale` and `wine_prefix`.
+        vars.extend(tab.env.iter().map(|(k, v)| (k.clone(), v.clone())));
+        vars.extend(item.env.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+        LaunchCommand {
+            program, args, cwd,
+            env: vars,
+            tuning: ProcessTuning::from_item(item),
+            errors, warnings,
+        }
+    }
+
+    pub fn to_command(&self) -> Command {
+
+        let mut command = Command::new(&self.program);
+        command
+            .args(&self.args)
+            .current_dir(&self.cwd)
+            .envs(&self.env)
+            // lead a new process group, so the game can be killed with everything it spawns.
+            .process_group(0);
+        self.tuning.apply(&mut command);
+        command
+    }
+
+    /// Describe what `to_command` would execute, one line per entry.
+    pub fn preview(&self) -> Vec<String> {
+
+        let mut lines = vec![
+            format!("program: {}", self.program),
+            format!("argv   : {:?}", self.args),
+            format!("cwd    : {}", self.cwd.display()),
+        ];
+
+        if self.env.is_empty() {
+            lines.push(String::from("env    : (inherited)"));
+        }
+
+        for (key, value) in self.env.iter() {
+            let line = match env::var(key) {
+                | Ok(ref old) if old == value => format!("env    = {}={}", key, value),
+                | Ok(old) => format!("env    ~ {}={} (was {})", key, value, old),
+                | Err(_)  => format!("env    + {}={}", key, value),
+            };
+            lines.push(line);
+        }
+
+        lines.extend(self.tuning.preview());
+        lines.extend(self.warnings.iter().map(|warning| format!("warning: {}", warning)));
+        lines.extend(self.errors.iter().map(|error| format!("error  : {}", error)));
+        lines
+    }
+}
+
+/// The runner of `item`, looked up in the item, then its tab, then the global setting.
+pub fn resolve_runner<'a>(setting: &'a SettingConfig, tab: &'a TabConfig, item: &'a ItemConfig) -> Option<&'a String> {
+
+    item.runner.as_ref()
+        .or(tab.runner.as_ref())
+        .or(setting.runner.as_ref())
+        .filter(|runner| !runner.is_empty())
+}
+
+/// Split a command line into words on whitespace, honouring single and double quotes.
+///
+/// Outside of single quotes, a backslash escapes a following space, quote or backslash.
+/// Other backslashes are kept, as in the Windows paths given to games.
+pub fn split_words(line: &str) -> Vec<String> {
+
+    let mut words = vec![];
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match quote {
+            | Some('\'') if ch == '\'' => quote = None,
+            | Some('\'') => current.push(ch),
+            | _ if ch == '\\' => {
+                let escaped = chars.next_if(|next| {
+                    *next == '\\' || *next == '"' || (quote.is_none() && (*next == '\'' || next.is_whitespace()))
+                });
+                current.push(escaped.unwrap_or(ch));
+                in_word = true;
+            },
+            | Some(q) if ch == q => quote = None,
+            | Some(_) => current.push(ch),
+            | None => {
+                if ch == '\'' || ch == '"' {
+                    quote = Some(ch);
+                    in_word = true;
+                } else if ch.is_whitespace() {
+                    if in_word {
+                        words.push(current.clone());
+                        current.clear();
+                        in_word = false;
+                    }
+                } else {
+                    current.push(ch);
+                    in_word = true;
+                }
+            },
+        }
+    }
+
+    if in_word {
+        words.push(current);
+    }
+
+    words
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn words(line: &str) -> Vec<String> {
+        split_words(line)
+    }
+
+    #[test]
+    fn splits_words_on_whitespace() {
+
+        assert_eq!(words(""), Vec::<String>::new());
+        assert_eq!(words("   \t "), Vec::<String>::new());
+        assert_eq!(words("  taskset  -c 0\tgamemoderun "), ["taskset", "-c", "0", "gamemoderun"]);
+    }
+
+    #[test]
+    fn keeps_quoted_words_together() {
+
+        assert_eq!(words("sh -c 'echo \"hi there\"'"), ["sh", "-c", "echo \"hi there\""]);
+        assert_eq!(words("\"/mnt/My Games/th08.exe\" -w"), ["/mnt/My Games/th08.exe", "-w"]);
+        assert_eq!(words("--title=\"Perfect Cherry\"' Blossom'"), ["--title=Perfect Cherry Blossom"]);
+        assert_eq!(words("'' \"\""), ["", ""]);
+        assert_eq!(words("'unterminated quote"), ["unterminated quote"]);
+    }
+
+    #[test]
+    fn unescapes_spaces_quotes_and_backslashes() {
+
+        assert_eq!(words("/mnt/My\\ Games/th08.exe -w"), ["/mnt/My Games/th08.exe", "-w"]);
+        assert_eq!(words("it\\'s \"say \\\"hi\\\"\""), ["it's", "say \"hi\""]);
+        assert_eq!(words("'C:\\Games\\' C:\\Games\\th08 C:\\\\Games"), ["C:\\Games\\", "C:\\Games\\th08", "C:\\Games"]);
+    }
+
+    #[test]
+    fn composes_wrappers_runner_game_arguments_and_environment_in_order() {
+
+        let setting = SettingConfig {
+            wrappers: vec![String::from("gamemoderun")],
+            runner: Some(String::from("wine")),
+            ..SettingConfig::default()
+        };
+        let tab = TabConfig {
+            name: String::from("Mainline"),
+            wrappers: vec![String::from("taskset -c 0")],
+            env: vec![(String::from("WINEDEBUG"), String::from("-all")), (String::from("LANG"), String::from("C"))]
+                .into_iter().collect(),
+            ..TabConfig::default()
+        };
+        let item = ItemConfig {
+            name: String::from("th08"),
+            path: String::from("/games/th08/th08.exe"),
+            args: vec![String::from("-w")],
+            wrappers: vec![String::from("strace -f -o '/tmp/th08 trace'")],
+            locale: Some(String::from("ja_JP.UTF-8")),
+            env: vec![(String::from("LANG"), String::from("ja_JP.SJIS"))].into_iter().collect(),
+            ..ItemConfig::default()
+        };
+
+        let command = LaunchCommand::compose(&setting, &tab, &item);
+
+        assert_eq!(command.program, "gamemoderun");
+        assert_eq!(command.args, ["taskset", "-c", "0", "strace", "-f", "-o", "/tmp/th08 trace", "wine", "/games/th08/th08.exe", "-w"]);
+        assert_eq!(command.cwd, PathBuf::from("/games/th08"));
+        // the tab and item variables win over the locale, the item over the tab.
+        let env: Vec<(&str, &str)> = command.env.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        assert_eq!(env, [("LANG", "ja_JP.SJIS"), ("LC_ALL", "ja_JP.UTF-8"), ("WINEDEBUG", "-all")]);
+        assert!(command.errors.is_empty());
+    }
+
+    #[test]
+    fn leaves_out_the_inherited_wrappers_and_an_empty_runner() {
+
+        let setting = SettingConfig {
+            wrappers: vec![String::from("gamemoderun")],
+            runner: Some(String::from("wine")),
+            ..SettingConfig::default()
+        };
+        let tab = TabConfig::default();
+        let item = ItemConfig {
+            path: String::from("/games/native/game"),
+            runner: Some(String::new()),
+            inherit_wrappers: Some(false),
+            wrappers: vec![String::from("mangohud")],
+            ..ItemConfig::default()
+        };
+
+        let command = LaunchCommand::compose(&setting, &tab, &item);
+
+        assert_eq!(command.program, "mangohud");
+        assert_eq!(command.args, ["/games/native/game"]);
+    }
+}
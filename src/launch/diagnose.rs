@@ -0,0 +1,226 @@
+
+use std::ffi::{ CStr, CString };
+use std::fmt;
+use std::io;
+use std::os::unix::ffi::OsStringExt;
+use std::os::unix::process::ExitStatusExt;
+use std::path::Path;
+use std::process::ExitStatus;
+
+use crate::launch::command::LaunchCommand;
+use crate::library::health;
+
+/// A human readable explanation of why a game did not run successfully.
+#[derive(Debug, Clone)]
+pub struct Failure {
+
+    pub summary: String,
+    pub suggestion: Option<String>,
+}
+
+impl fmt::Display for Failure {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        match self.suggestion {
+            | Some(ref suggestion) => write!(f, "{} ({})", self.summary, suggestion),
+            | None => write!(f, "{}", self.summary),
+        }
+    }
+}
+
+/// Explain a finished game, or return `None` if it exited successfully.
+pub fn diagnose_status(status: &ExitStatus, command: &LaunchCommand) -> Option<Failure> {
+
+    if status.success() {
+        return None
+    }
+
+    if let Some(signal) = status.signal() {
+
+        let summary = match signal_name(signal) {
+            | Some(name) => format!("killed by signal {} ({})", signal, name),
+            | None => format!("killed by signal {}", signal),
+        };
+        let summary = if status.core_dumped() {
+            format!("{}, core dumped", summary)
+        } else {
+            summary
+        };
+
+        return Some(Failure { summary, suggestion: None })
+    }
+
+    let code = status.code()?;
+    let summary = format!("exited with code {}", code);
+
+    let suggestion = if is_wine(command) {
+        wine_exit_reason(code).map(String::from)
+    } else {
+        None
+    };
+
+    Some(Failure { summary, suggestion })
+}
+
+/// Explain why the game process could not be started at all.
+pub fn diagnose_spawn(error: &io::Error, command: &LaunchCommand) -> Failure {
+
+    // ENOEXEC: the file exists but the kernel does not know how to run it.
+    const ENOEXEC: i32 = 8;
+
+    let program = &command.program;
+
+    if error.raw_os_error() == Some(ENOEXEC) {
+        return Failure {
+            summary: format!("`{}` is not an executable this system can run", program),
+            suggestion: Some(String::from("set `runner = \"wine\"` for Windows games")),
+        }
+    }
+
+    match error.kind() {
+        | io::ErrorKind::NotFound => {
+            let suggestion = if Path::new(program).is_absolute() || program.contains('/') {
+                format!("check that `{}` exists and its drive is mounted", program)
+            } else {
+                format!("install `{}` or use its full path", program)
+            };
+
+            Failure {
+                summary: format!("`{}` was not found", program),
+                suggestion: Some(suggestion),
+            }
+        },
+        // `setpriority` fails with EACCES too, but only blame it once the program itself is runnable.
+        | io::ErrorKind::PermissionDenied if command.tuning.nice.is_some_and(|nice| nice < 0) && is_executable(command) => {
+            Failure {
+                summary: String::from("not allowed to raise the priority of the game"),
+                suggestion: Some(String::from("negative `nice` values need CAP_SYS_NICE or a higher RLIMIT_NICE")),
+            }
+        },
+        | io::ErrorKind::PermissionDenied => {
+            Failure {
+                summary: format!("permission denied to execute `{}`", program),
+                suggestion: Some(format!("run `chmod +x {}` or set a `runner`", program)),
+            }
+        },
+        | _ => {
+            Failure {
+                summary: format!("`{}` failed to start: {}", program, error),
+                suggestion: None,
+            }
+        },
+    }
+}
+
+fn is_executable(command: &LaunchCommand) -> bool {
+
+    let mut path = health::locate(&command.program);
+    if path.is_relative() {
+        path = command.cwd.join(path);
+    }
+
+    CString::new(path.into_os_string().into_vec())
+        .map(|path| unsafe { libc::access(path.as_ptr(), libc::X_OK) } == 0)
+        .unwrap_or(false)
+}
+
+fn is_wine(command: &LaunchCommand) -> bool {
+
+    Path::new(&command.program).file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with("wine"))
+}
+
+/// The description of `signal` by the C library, such as `Segmentation fault`.
+fn signal_name(signal: i32) -> Option<String> {
+
+    let name = unsafe { libc::strsignal(signal) };
+    if name.is_null() {
+        return None
+    }
+    Some(unsafe { CStr::from_ptr(name) }.to_string_lossy().into_owned())
+}
+
+/// The exit codes of Wine that tell how a Windows program ended.
+///
+/// Wine ends a program that dies of an unhandled exception with `NtTerminateProcess`, the exception
+/// code as its status (`UnhandledExceptionFilter` in kernelbase), and so does its loader when an import
+/// is missing (`LdrInitializeThunk` in ntdll). The Unix exit status keeps the low byte of that NTSTATUS,
+/// whose values come from `ntstatus.h`. The statuses whose low byte is a common exit code, such as
+/// `STATUS_BREAKPOINT` (0x80000003), are left out.
+fn wine_exit_reason(code: i32) -> Option<&'static str> {
+
+    let reason = match code {
+        | 0x05 => "access violation (STATUS_ACCESS_VIOLATION, 0xC0000005), the game crashed",
+        // raised by the `/GS` checks of the Visual C++ runtime the games are built with.
+        | 0x09 => "stack buffer overrun (STATUS_STACK_BUFFER_OVERRUN, 0xC0000409)",
+        | 0x1D => "illegal instruction (STATUS_ILLEGAL_INSTRUCTION, 0xC000001D)",
+        | 0x35 => "a DLL was not found (STATUS_DLL_NOT_FOUND, 0xC0000135), check the game path and the Wine prefix",
+        | 0x94 => "integer division by zero (STATUS_INTEGER_DIVIDE_BY_ZERO, 0xC0000094)",
+        | _    => return None,
+    };
+
+    Some(reason)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::launch::tuning::ProcessTuning;
+
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+
+    fn command(program: &str) -> LaunchCommand {
+
+        LaunchCommand {
+            program: program.to_owned(),
+            args: vec![],
+            cwd: PathBuf::from("/"),
+            env: BTreeMap::new(),
+            tuning: ProcessTuning::default(),
+            errors: vec![],
+            warnings: vec![],
+        }
+    }
+
+    fn exited(code: i32) -> ExitStatus {
+        ExitStatus::from_raw(code << 8)
+    }
+
+    #[test]
+    fn explains_signals() {
+
+        let failure = diagnose_status(&ExitStatus::from_raw(libc::SIGSEGV), &command("/games/th08")).unwrap();
+        assert_eq!(failure.summary, "killed by signal 11 (Segmentation fault)");
+
+        let failure = diagnose_status(&ExitStatus::from_raw(libc::SIGABRT | 0x80), &command("/games/th08")).unwrap();
+        assert_eq!(failure.summary, "killed by signal 6 (Aborted), core dumped");
+    }
+
+    #[test]
+    fn explains_exit_codes() {
+
+        assert!(diagnose_status(&exited(0), &command("/games/th08")).is_none());
+
+        let failure = diagnose_status(&exited(5), &command("/games/th08")).unwrap();
+        assert_eq!(failure.summary, "exited with code 5");
+        assert_eq!(failure.suggestion, None);
+    }
+
+    #[test]
+    fn explains_the_exit_codes_of_wine() {
+
+        let failure = diagnose_status(&exited(0x05), &command("/usr/bin/wine")).unwrap();
+        assert_eq!(failure.to_string(),
+            "exited with code 5 (access violation (STATUS_ACCESS_VIOLATION, 0xC0000005), the game crashed)");
+
+        let failure = diagnose_status(&exited(0x35), &command("wine64")).unwrap();
+        assert!(failure.suggestion.unwrap().contains("STATUS_DLL_NOT_FOUND"));
+
+        let failure = diagnose_status(&exited(3), &command("wine")).unwrap();
+        assert_eq!(failure.suggestion, None);
+    }
+}
@@ -0,0 +1,67 @@
+
+use std::collections::{ HashMap, VecDeque };
+use std::time::SystemTime;
+
+use crate::config::tab::{ TabConfig, ItemConfig };
+use crate::launch::diagnose::Failure;
+
+/// Number of failures kept for each game.
+const MAX_RECORDS: usize = 8;
+
+pub struct FailureRecord {
+
+    pub at: SystemTime,
+    pub failure: Failure,
+}
+
+/// The recent failures of every game launched in this session.
+#[derive(Default)]
+pub struct FailureHistory {
+
+    records: HashMap<String, VecDeque<FailureRecord>>,
+}
+
+impl FailureHistory {
+
+    pub fn push(&mut self, tab: &TabConfig, item: &ItemConfig, failure: Failure) {
+
+        let records = self.records.entry(FailureHistory::key(tab, item))
+            .or_default();
+
+        if records.len() == MAX_RECORDS {
+            records.pop_front();
+        }
+        records.push_back(FailureRecord { at: SystemTime::now(), failure });
+    }
+
+    /// Describe the failures of `item`, most recent first.
+    pub fn describe(&self, tab: &TabConfig, item: &ItemConfig) -> Vec<String> {
+
+        match self.records.get(&FailureHistory::key(tab, item)) {
+            | Some(records) => {
+                records.iter().rev().map(|record| {
+                    let elapsed = record.at.elapsed()
+                        .map(|d| d.as_secs())
+                        .unwrap_or(0);
+                    format!("{} ago: {}", format_elapsed(elapsed), record.failure)
+                }).collect()
+            },
+            | None => vec![],
+        }
+    }
+
+    fn key(tab: &TabConfig, item: &ItemConfig) -> String {
+        format!("{}/{}", tab.name, item.name)
+    }
+}
+
+fn format_elapsed(secs: u64) -> String {
+
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{}h", secs / 3600)
+    }
+}
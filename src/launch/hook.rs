@@ -0,0 +1,208 @@
+
+use std::process::Command;
+use std::sync::mpsc::{ self, Receiver };
+use std::thread;
+
+use crate::config::hook::HookPolicy;
+use crate::config::tab::{ TabConfig, ItemConfig };
+use crate::launch::session::SessionLog;
+
+#[derive(Debug, Clone, Copy)]
+pub enum HookStage {
+    PreLaunch,
+    PostLaunch,
+}
+
+impl HookStage {
+
+    fn name(&self) -> &'static str {
+        match self {
+            | HookStage::PreLaunch  => "pre_launch",
+            | HookStage::PostLaunch => "post_launch",
+        }
+    }
+}
+
+/// The variables exported to every hook command.
+#[derive(Clone)]
+pub struct HookVars {
+
+    pub game_name: String,
+    pub game_path: String,
+    pub tab_name : String,
+    /// Only known for `post_launch` commands. `None` if the game was killed by a signal.
+    pub exit_code: Option<i32>,
+}
+
+impl HookVars {
+
+    pub fn new(tab: &TabConfig, item: &ItemConfig) -> HookVars {
+
+        HookVars {
+            game_name: item.name.clone(),
+            game_path: item.path.clone(),
+            tab_name : tab.name.clone(),
+            exit_code: None,
+        }
+    }
+
+    fn apply(&self, stage: HookStage, command: &mut Command) {
+
+        command
+            .env("THL_HOOK_STAGE", stage.name())
+            .env("THL_GAME_NAME", &self.game_name)
+            .env("THL_GAME_PATH", &self.game_path)
+            .env("THL_TAB_NAME", &self.tab_name);
+
+        if let HookStage::PostLaunch = stage {
+            let exit_code = self.exit_code.map(|code| code.to_string()).unwrap_or_default();
+            command.env("THL_EXIT_CODE", exit_code);
+        }
+    }
+}
+
+/// Run the hook `commands` one by one through `sh -c`, capturing their output to the session log.
+///
+/// Return the warnings to show to the user, or the reason to abort the launch.
+pub fn run_hooks(stage: HookStage, commands: &[&String], vars: &HookVars, policy: HookPolicy, log: &mut SessionLog) -> Result<Vec<String>, String> {
+
+    let mut warnings = vec![];
+
+    for hook in commands {
+
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(hook);
+        vars.apply(stage, &mut command);
+
+        let failure = match command.output() {
+            | Ok(output) => {
+                log.record(&format!("{} `{}` exited with {}", stage.name(), hook, output.status));
+                log.record(&String::from_utf8_lossy(&output.stdout));
+                log.record(&String::from_utf8_lossy(&output.stderr));
+
+                if output.status.success() {
+                    None
+                } else {
+                    Some(format!("{} `{}` failed ({})", stage.name(), hook, output.status))
+                }
+            },
+            | Err(e) => {
+                log.record(&format!("{} `{}` could not be started: {}", stage.name(), hook, e));
+                Some(format!("{} `{}` could not be started: {}", stage.name(), hook, e))
+            },
+        };
+
+        if let Some(failure) = failure {
+            match (policy, stage) {
+                | (HookPolicy::Abort, HookStage::PreLaunch) => {
+                    log.record("launch aborted by hook policy.");
+                    return Err(failure)
+                },
+                // the game has already exited, so there is nothing left to abort.
+                | (HookPolicy::Abort, HookStage::PostLaunch)
+                | (HookPolicy::Warn, _) => warnings.push(failure),
+                | (HookPolicy::Ignore, _) => {},
+            }
+        }
+    }
+
+    Ok(warnings)
+}
+
+/// Run the hook `commands` like `run_hooks`, but on a thread, without waiting for them.
+///
+/// The warnings are sent once every command has exited.
+pub fn spawn_hooks(stage: HookStage, commands: Vec<String>, vars: HookVars, policy: HookPolicy, mut log: SessionLog) -> Receiver<Vec<String>> {
+
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let commands: Vec<&String> = commands.iter().collect();
+        let warnings = run_hooks(stage, &commands, &vars, policy, &mut log)
+            .unwrap_or_else(|reason| vec![reason]);
+        let _ = sender.send(warnings);
+    });
+
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::utils::testing::scratch_dir;
+
+    use std::fs;
+    use std::time::Duration;
+
+    fn vars() -> HookVars {
+        HookVars {
+            game_name: String::from("th08"),
+            game_path: String::from("/games/th08/th08.exe"),
+            tab_name : String::from("Mainline"),
+            exit_code: Some(3),
+        }
+    }
+
+    fn run(name: &str, stage: HookStage, commands: &[&str], policy: HookPolicy) -> Result<Vec<String>, String> {
+
+        let dir = scratch_dir(name);
+        let mut log = SessionLog::open_at(&dir.join("session.log"));
+        let commands: Vec<String> = commands.iter().map(|command| command.to_string()).collect();
+        let commands: Vec<&String> = commands.iter().collect();
+        let result = run_hooks(stage, &commands, &vars(), policy, &mut log);
+        let _ = fs::remove_dir_all(&dir);
+        result
+    }
+
+    #[test]
+    fn aborts_only_the_launch_on_a_failed_pre_launch_hook() {
+
+        assert_eq!(run("hook-1", HookStage::PreLaunch, &["true", "exit 2", "exit 3"], HookPolicy::Abort),
+            Err(String::from("pre_launch `exit 2` failed (exit status: 2)")));
+        assert_eq!(run("hook-2", HookStage::PostLaunch, &["exit 2", "true"], HookPolicy::Abort),
+            Ok(vec![String::from("post_launch `exit 2` failed (exit status: 2)")]));
+    }
+
+    #[test]
+    fn warns_about_or_ignores_failed_hooks() {
+
+        assert_eq!(run("hook-3", HookStage::PreLaunch, &["exit 1", "exit 2"], HookPolicy::Warn), Ok(vec![
+            String::from("pre_launch `exit 1` failed (exit status: 1)"),
+            String::from("pre_launch `exit 2` failed (exit status: 2)"),
+        ]));
+        assert_eq!(run("hook-4", HookStage::PreLaunch, &["exit 1"], HookPolicy::Ignore), Ok(vec![]));
+        assert_eq!(run("hook-5", HookStage::PostLaunch, &["exit 1"], HookPolicy::Ignore), Ok(vec![]));
+    }
+
+    #[test]
+    fn exports_the_game_to_hooks() {
+
+        let dir = scratch_dir("hook-vars");
+        let out = dir.join("vars");
+        let mut log = SessionLog::open_at(&dir.join("session.log"));
+        let command = format!("echo \"$THL_HOOK_STAGE $THL_TAB_NAME $THL_GAME_NAME $THL_GAME_PATH $THL_EXIT_CODE\" > '{}'", out.display());
+
+        assert_eq!(run_hooks(HookStage::PostLaunch, &[&command], &vars(), HookPolicy::Warn, &mut log), Ok(vec![]));
+        assert_eq!(fs::read_to_string(&out).unwrap(), "post_launch Mainline th08 /games/th08/th08.exe 3\n");
+        assert!(fs::read_to_string(dir.join("session.log")).unwrap().contains("post_launch `echo"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn runs_hooks_in_the_background() {
+
+        let dir = scratch_dir("hook-spawn");
+        let log = SessionLog::open_at(&dir.join("session.log"));
+        let commands = vec![String::from("sleep 1"), String::from("exit 4")];
+
+        let receiver = spawn_hooks(HookStage::PostLaunch, commands, vars(), HookPolicy::Warn, log);
+
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(10)),
+            Ok(vec![String::from("post_launch `exit 4` failed (exit status: 4)")]));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,181 @@
+
+use std::fs::{ self, OpenOptions };
+use std::io::{ self, Read, Write };
+use std::path::{ Path, PathBuf };
+
+use crate::launch::process;
+
+/// A lock file marking a game as running, so other launcher instances do not start it again.
+pub struct InstanceLock {
+
+    path: PathBuf,
+}
+
+pub enum LockError {
+    /// The game is already running with this pid.
+    Held(u32),
+    Io(io::Error),
+}
+
+impl InstanceLock {
+
+    pub fn acquire(dir: &Path, key: &str) -> Result<InstanceLock, LockError> {
+
+        fs::create_dir_all(dir).map_err(LockError::Io)?;
+
+        let path = dir.join(format!("{}.lock", file_name(key)));
+
+        // retry once, after removing a lock left by a launcher that did not exit cleanly.
+        for _ in 0..2 {
+
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                | Ok(mut file) => {
+                    let _ = file.write_all(owner(std::process::id()).as_bytes());
+                    return Ok(InstanceLock { path })
+                },
+                | Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    match read_owner(&path) {
+                        | Some((pid, start)) if is_alive(pid, start) => return Err(LockError::Held(pid)),
+                        | _ => fs::remove_file(&path).map_err(LockError::Io)?,
+                    }
+                },
+                | Err(e) => return Err(LockError::Io(e)),
+            }
+        }
+
+        Err(LockError::Io(io::Error::new(io::ErrorKind::AlreadyExists, "the lock file keeps reappearing")))
+    }
+
+    /// Record the pid of the game once it is spawned.
+    pub fn set_pid(&self, pid: u32) {
+        let _ = fs::write(&self.path, owner(pid));
+    }
+}
+
+impl Drop for InstanceLock {
+
+    fn drop(&mut self) {
+
+        // the game outlives a launcher that quits while it runs, and keeps guarding it until it exits.
+        match read_owner(&self.path) {
+            | Some((pid, start)) if pid != std::process::id() && is_alive(pid, start) => {},
+            | _ => { let _ = fs::remove_file(&self.path); },
+        }
+    }
+}
+
+/// The lock file name of `key`: its letters, digits and dashes, and the bytes of the other characters
+/// as `_` and two hex digits, so that every key has its own file.
+fn file_name(key: &str) -> String {
+
+    let mut name = String::new();
+    for ch in key.chars() {
+        if ch.is_alphanumeric() || ch == '-' {
+            name.push(ch);
+        } else {
+            let mut bytes = [0; 4];
+            for byte in ch.encode_utf8(&mut bytes).bytes() {
+                name.push_str(&format!("_{:02X}", byte));
+            }
+        }
+    }
+    name
+}
+
+/// The content of a lock file: the pid and start time of its owner, so a reused pid is not mistaken for it.
+fn owner(pid: u32) -> String {
+
+    match process::read_start_time(pid) {
+        | Some(start) => format!("{} {}", pid, start),
+        | None => pid.to_string(),
+    }
+}
+
+fn read_owner(path: &Path) -> Option<(u32, Option<u64>)> {
+
+    let mut content = String::new();
+    fs::File::open(path).ok()?.read_to_string(&mut content).ok()?;
+
+    let mut fields = content.split_whitespace();
+    let pid = fields.next()?.parse().ok()?;
+    let start = fields.next().and_then(|start| start.parse().ok());
+    Some((pid, start))
+}
+
+/// Whether `pid` still runs and, when its `start` time was recorded, is still the same process.
+pub fn is_alive(pid: u32, start: Option<u64>) -> bool {
+
+    match (process::read_start_time(pid), start) {
+        | (Some(current), Some(start)) => current == start,
+        | (Some(_), None) => true,
+        | (None, _) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::process::Command;
+
+    use crate::utils::testing::scratch_dir;
+
+    #[test]
+    fn keys_do_not_share_lock_files() {
+
+        assert_eq!(file_name("Mainline/th08"), "Mainline_2Fth08");
+        assert_eq!(file_name("a/b_c"), "a_2Fb_5Fc");
+        assert_eq!(file_name("a_b/c"), "a_5Fb_2Fc");
+        assert_eq!(file_name("東方/th 08"), "東方_2Fth_2008");
+
+        let dir = scratch_dir("distinct-locks");
+        let first = InstanceLock::acquire(&dir, "a/b_c").ok().unwrap();
+        let second = InstanceLock::acquire(&dir, "a_b/c").ok().unwrap();
+        assert_ne!(first.path, second.path);
+
+        drop((first, second));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn stale_and_reused_pids_are_free() {
+
+        let dir = scratch_dir("stale-lock");
+        let lock = InstanceLock::acquire(&dir, "th08").ok().unwrap();
+        let path = lock.path.clone();
+        drop(lock);
+        assert!(!path.exists());
+
+        // pid 1 is alive, but started long before the recorded time: the pid was reused.
+        let reused = process::read_start_time(1).unwrap() + 1;
+        fs::write(&path, format!("1 {}", reused)).unwrap();
+        assert!(InstanceLock::acquire(&dir, "th08").is_ok());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn lock_outlives_the_launcher_while_the_game_runs() {
+
+        let dir = scratch_dir("running-lock");
+        let mut game = Command::new("sleep").arg("5").spawn().unwrap();
+
+        let lock = InstanceLock::acquire(&dir, "th08").ok().unwrap();
+        lock.set_pid(game.id());
+        let path = lock.path.clone();
+        drop(lock);
+
+        assert!(path.exists());
+        match InstanceLock::acquire(&dir, "th08") {
+            | Err(LockError::Held(pid)) => assert_eq!(pid, game.id()),
+            | _ => panic!("the lock of a running game was taken"),
+        }
+
+        let _ = game.kill();
+        let _ = game.wait();
+        assert!(InstanceLock::acquire(&dir, "th08").is_ok());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,45 @@
+
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// Whether `locale` is installed according to `locale -a`, or `None` when that cannot be told.
+pub fn is_installed(locale: &str) -> Option<bool> {
+
+    let wanted = normalize(locale);
+    installed_locales().map(|installed| installed.contains(&wanted))
+}
+
+/// The normalized output of `locale -a`, asked once per process since it does not change while we run.
+fn installed_locales() -> Option<&'static Vec<String>> {
+
+    static INSTALLED: OnceLock<Option<Vec<String>>> = OnceLock::new();
+
+    INSTALLED.get_or_init(|| {
+
+        let output = Command::new("locale").arg("-a").output().ok()?;
+        if !output.status.success() {
+            return None
+        }
+
+        let installed = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| normalize(line.trim()))
+            .collect();
+        Some(installed)
+    }).as_ref()
+}
+
+/// `locale -a` spells `ja_JP.UTF-8` as `ja_JP.utf8`, so compare codesets without case and dashes.
+fn normalize(locale: &str) -> String {
+
+    match locale.find('.') {
+        | Some(dot) => {
+            let codeset: String = locale[dot + 1..].chars()
+                .filter(|ch| *ch != '-')
+                .flat_map(char::to_lowercase)
+                .collect();
+            format!("{}.{}", &locale[..dot], codeset)
+        },
+        | None => locale.to_owned(),
+    }
+}
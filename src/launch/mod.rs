@@ -0,0 +1,441 @@
+
+pub mod hook;
+pub mod session;
+pub mod command;
+pub mod diagnose;
+pub mod history;
+pub mod tuning;
+pub mod instance;
+pub mod process;
+pub mod watchdog;
+pub mod locale;
+pub mod prefix;
+pub mod winpath;
+
+use std::path::Path;
+use std::process::{ Child, ExitStatus };
+use std::io;
+use std::sync::mpsc::{ Receiver, TryRecvError };
+use std::time::Instant;
+
+use crate::config::hook::HookPolicy;
+use crate::config::setting::SettingConfig;
+use crate::config::tab::{ TabConfig, ItemConfig };
+use crate::launch::command::LaunchCommand;
+use crate::launch::diagnose::Failure;
+use crate::launch::history::FailureHistory;
+use crate::launch::hook::{ HookStage, HookVars };
+use crate::launch::instance::{ InstanceLock, LockError };
+use crate::launch::prefix::{ PrefixAction, WinePrefix };
+use crate::launch::process::{ ProcessInfo, ProcessSignal };
+use crate::launch::session::SessionLog;
+use crate::launch::watchdog::{ StopReason, Watchdog };
+use crate::launch::winpath::PathMap;
+
+pub struct Launcher {
+
+    setting: SettingConfig,
+    log: SessionLog,
+    history: FailureHistory,
+    running: Vec<RunningGame>,
+    pending: Vec<PendingRestart>,
+    /// The `post_launch` hooks still running, by the name of their game.
+    hooks: Vec<(String, Receiver<Vec<String>>)>,
+}
+
+/// A game spawned in the background, waiting to be reaped by `Launcher::poll`.
+struct RunningGame {
+
+    key: String,
+    tab: TabConfig,
+    item: ItemConfig,
+    child: Child,
+    started: Instant,
+    command: LaunchCommand,
+    vars: HookVars,
+    policy: HookPolicy,
+    watchdog: Watchdog,
+    /// Number of automatic restarts in a row that led to this run.
+    restarts: u32,
+    _lock: Option<InstanceLock>,
+}
+
+/// A game waiting to be restarted by the watchdog.
+struct PendingRestart {
+
+    tab: TabConfig,
+    item: ItemConfig,
+    at: Instant,
+    attempt: u32,
+}
+
+pub enum LaunchOutcome {
+    /// The game is running in the background. Its report comes later from `Launcher::poll`.
+    Started { pid: u32, warnings: Vec<String> },
+    /// The game is already running with this pid, so it was not started again.
+    AlreadyRunning { pid: u32 },
+    /// The game did not start at all.
+    Finished(LaunchReport),
+}
+
+pub struct LaunchReport {
+
+    pub name: String,
+    /// Why the game did not run successfully, if it did not.
+    pub failure: Option<Failure>,
+    /// Hook failures that should be shown to the user.
+    pub warnings: Vec<String>,
+}
+
+impl Launcher {
+
+    pub fn new(setting: SettingConfig) -> Launcher {
+
+        Launcher {
+            setting,
+            log: SessionLog::open(),
+            history: FailureHistory::default(),
+            running: vec![],
+            pending: vec![],
+            hooks: vec![],
+        }
+    }
+
+    pub fn setting(&self) -> &SettingConfig {
+        &self.setting
+    }
+
+    pub fn compose(&self, tab: &TabConfig, item: &ItemConfig) -> LaunchCommand {
+        LaunchCommand::compose(&self.setting, tab, &item.resolve_variant())
+    }
+
+    /// Run the `pre_launch` hooks of `item`, then start its game in the background.
+    pub fn launch(&mut self, tab: &TabConfig, item: &ItemConfig) -> LaunchOutcome {
+
+        let item = &PathMap::from_setting(&self.setting).localize(&item.resolve_variant());
+        let key = instance_key(tab, item);
+        let single_instance = !item.allow_multiple_instances
+            .unwrap_or(self.setting.allow_multiple_instances);
+
+        if single_instance {
+            if let Some(game) = self.running.iter().find(|game| game.key == key) {
+                return LaunchOutcome::AlreadyRunning { pid: game.child.id() }
+            }
+        }
+
+        let lock = match self.setting.instance_lock_dir {
+            | Some(ref dir) if single_instance => {
+                match InstanceLock::acquire(Path::new(dir), &key) {
+                    | Ok(lock) => Some(lock),
+                    | Err(LockError::Held(pid)) => return LaunchOutcome::AlreadyRunning { pid },
+                    | Err(LockError::Io(e)) => {
+                        // a broken lock directory should not prevent playing.
+                        self.log.record(&format!("failed to lock `{}`: {}", item.name, e));
+                        None
+                    },
+                }
+            },
+            | _ => None,
+        };
+
+        let policy = item.hook_policy
+            .or(tab.hook_policy)
+            .unwrap_or(self.setting.hook_policy);
+        let vars = HookVars::new(tab, item);
+
+        self.log.record(&format!("launch `{}` ({})", item.name, item.path));
+
+        // global hooks go first, then the tab ones, then the item ones.
+        let pre_launch: Vec<&String> = inherited_hooks(&self.setting.pre_launch, &tab.pre_launch, item)
+            .chain(item.pre_launch.iter())
+            .collect();
+
+        let mut warnings = match hook::run_hooks(HookStage::PreLaunch, &pre_launch, &vars, policy, &mut self.log) {
+            | Ok(warnings) => warnings,
+            | Err(reason) => {
+                let report = LaunchReport { name: item.name.clone(), failure: None, warnings: vec![reason] };
+                return LaunchOutcome::Finished(report)
+            },
+        };
+
+        let command = LaunchCommand::compose(&self.setting, tab, item);
+        if let Some(error) = command.errors.first() {
+            self.log.record(&format!("`{}` cannot start: {}", item.name, error));
+            let failure = Failure {
+                summary: error.clone(),
+                suggestion: Some(String::from("check the launch settings of the game")),
+            };
+            let mut report = self.finish(tab, item, vars, policy, Some(failure));
+            report.warnings = warnings;
+
+            return LaunchOutcome::Finished(report)
+        }
+
+        if let Some(Ok(prefix)) = WinePrefix::for_item(&self.setting, tab, item) {
+            if !prefix.exists() {
+                self.log.record(&format!("creating wine prefix {}", prefix.path.display()));
+
+                if let Err(e) = prefix.create(&self.setting, tab, item) {
+                    self.log.record(&format!("`{}` cannot start: {}", item.name, e));
+                    let failure = Failure {
+                        summary: format!("failed to create the wine prefix `{}`: {}", prefix.name, e),
+                        suggestion: Some(String::from("check that the runner can run `wineboot`")),
+                    };
+                    let mut report = self.finish(tab, item, vars, policy, Some(failure));
+                    report.warnings = warnings;
+
+                    return LaunchOutcome::Finished(report)
+                }
+            }
+
+            if let Err(e) = prefix.record_user(&key) {
+                self.log.record(&format!("failed to record `{}` as a user of {}: {}", key, prefix.name, e));
+            }
+        }
+
+        for problem in command.tuning.problems.iter().chain(command.warnings.iter()) {
+            self.log.record(&format!("`{}` {}", item.name, problem));
+            warnings.push(problem.clone());
+        }
+
+        match command.to_command().spawn() {
+            | Ok(child) => {
+                let pid = child.id();
+                self.log.record(&format!("`{}` started with pid {}", item.name, pid));
+
+                if let Some(ref lock) = lock {
+                    lock.set_pid(pid);
+                }
+
+                self.running.push(RunningGame {
+                    key,
+                    tab: tab.clone(),
+                    item: item.clone(),
+                    child, command, vars, policy,
+                    started: Instant::now(),
+                    watchdog: Watchdog::from_item(item),
+                    restarts: 0,
+                    _lock: lock,
+                });
+
+                LaunchOutcome::Started { pid, warnings }
+            },
+            | Err(e) => {
+                self.log.record(&format!("`{}` failed to start: {}", item.name, e));
+                let failure = diagnose::diagnose_spawn(&e, &command);
+                let mut report = self.finish(tab, item, vars, policy, Some(failure));
+                report.warnings = warnings;
+
+                LaunchOutcome::Finished(report)
+            },
+        }
+    }
+
+    /// Enforce the watchdog settings, reap the games that have exited since the last call
+    /// (starting their `post_launch` hooks), report the hooks that have finished
+    /// and start the restarts that are due.
+    pub fn poll(&mut self) -> Vec<LaunchReport> {
+
+        for game in self.running.iter_mut() {
+            let pid = game.child.id();
+            if let Some(signal) = game.watchdog.inspect(pid, game.started) {
+                let reason = game.watchdog.stop_reason().unwrap_or(StopReason::User);
+                self.log.record(&format!("watchdog: `{}` (pid {}) stopped for {:?}, sending {:?}",
+                    game.item.name, pid, reason, signal));
+                if let Err(e) = process::send_signal(pid, signal) {
+                    self.log.record(&format!("watchdog: failed to signal pid {}: {}", pid, e));
+                }
+            }
+        }
+
+        let mut exited = vec![];
+
+        let mut index = 0;
+        while index < self.running.len() {
+            match self.running[index].child.try_wait() {
+                | Ok(Some(status)) => exited.push((self.running.remove(index), Ok(status))),
+                | Ok(None) => index += 1,
+                | Err(e) => exited.push((self.running.remove(index), Err(e))),
+            }
+        }
+
+        let mut reports: Vec<LaunchReport> = exited.into_iter()
+            .map(|(game, status)| self.reap(game, status))
+            .collect();
+
+        self.hooks.retain(|(name, receiver)| match receiver.try_recv() {
+            | Ok(warnings) => {
+                if !warnings.is_empty() {
+                    reports.push(LaunchReport { name: name.clone(), failure: None, warnings });
+                }
+                false
+            },
+            | Err(TryRecvError::Empty) => true,
+            | Err(TryRecvError::Disconnected) => false,
+        });
+
+        let now = Instant::now();
+        let (due, pending) = self.pending.drain(..).partition(|restart| restart.at <= now);
+        self.pending = pending;
+
+        for restart in due.into_iter() {
+            self.log.record(&format!("watchdog: restarting `{}` (attempt {})", restart.item.name, restart.attempt));
+
+            match self.launch(&restart.tab, &restart.item) {
+                | LaunchOutcome::Started { .. } => {
+                    if let Some(game) = self.running.last_mut() {
+                        game.restarts = restart.attempt;
+                    }
+                },
+                | LaunchOutcome::AlreadyRunning { .. } => {},
+                | LaunchOutcome::Finished(report) => reports.push(report),
+            }
+        }
+
+        reports
+    }
+
+    /// Whether no game nor hook is running, and no game is waiting to be restarted.
+    pub fn is_idle(&self) -> bool {
+        self.running.is_empty() && self.pending.is_empty() && self.hooks.is_empty()
+    }
+
+    /// Kill the running copy of `item`. Return false if it is not running.
+    pub fn kill(&mut self, tab: &TabConfig, item: &ItemConfig) -> bool {
+
+        let key = instance_key(tab, item);
+
+        match self.running.iter_mut().find(|game| game.key == key) {
+            | Some(game) => {
+                self.log.record(&format!("killing `{}` (pid {})", item.name, game.child.id()));
+                game.watchdog.stop_by_user();
+                game.child.kill().is_ok()
+            },
+            | None => false,
+        }
+    }
+
+    /// Snapshot the games that are still running.
+    pub fn processes(&self) -> Vec<ProcessInfo> {
+
+        self.running.iter()
+            .map(|game| ProcessInfo::read(&game.item.name, game.child.id(), game.started.elapsed()))
+            .collect()
+    }
+
+    /// Send `signal` to a running game. The exit is reported later by `poll`.
+    pub fn signal(&mut self, pid: u32, signal: ProcessSignal) -> io::Result<()> {
+
+        let game = self.running.iter_mut().find(|game| game.child.id() == pid)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "the game is not running anymore"))?;
+
+        self.log.record(&format!("sending {:?} to `{}` (pid {})", signal, game.item.name, pid));
+        game.watchdog.stop_by_user();
+        process::send_signal(pid, signal)
+    }
+
+    /// The managed Wine prefix of `item`, if it has one.
+    pub fn prefix(&self, tab: &TabConfig, item: &ItemConfig) -> Option<Result<WinePrefix, String>> {
+        WinePrefix::for_item(&self.setting, tab, &item.resolve_variant())
+    }
+
+    /// Reset or delete the managed Wine prefix of `item`, unless a running game uses it.
+    pub fn manage_prefix(&mut self, tab: &TabConfig, item: &ItemConfig, action: PrefixAction) -> Result<String, String> {
+
+        let item = &item.resolve_variant();
+        let prefix = WinePrefix::for_item(&self.setting, tab, item)
+            .ok_or_else(|| format!("{} has no managed wine prefix.", item.name))??;
+
+        if let Some(game) = self.running.iter().find(|game| prefix.is_used_by(&game.command)) {
+            return Err(format!("{} is still running in the prefix `{}`.", game.item.name, prefix.name))
+        }
+
+        self.log.record(&format!("{:?} wine prefix {}", action, prefix.path.display()));
+        prefix.delete()
+            .map_err(|e| format!("failed to delete {}: {}", prefix.path.display(), e))?;
+
+        match action {
+            | PrefixAction::Delete => Ok(format!("Deleted the prefix `{}`.", prefix.name)),
+            | PrefixAction::Reset => {
+                prefix.create(&self.setting, tab, item)?;
+                Ok(format!("Reset the prefix `{}`.", prefix.name))
+            },
+        }
+    }
+
+    /// The recent failures of `item`, most recent first.
+    pub fn failures(&self, tab: &TabConfig, item: &ItemConfig) -> Vec<String> {
+        self.history.describe(tab, item)
+    }
+
+    fn reap(&mut self, game: RunningGame, status: io::Result<ExitStatus>) -> LaunchReport {
+
+        let RunningGame { tab, item, command, mut vars, policy, watchdog, restarts, started, .. } = game;
+
+        let failure = match status {
+            | Ok(status) => {
+                self.log.record(&format!("`{}` exited with {}", item.name, status));
+                vars.exit_code = status.code();
+                diagnose::diagnose_status(&status, &command)
+            },
+            | Err(e) => {
+                self.log.record(&format!("`{}` could not be waited for: {}", item.name, e));
+                Some(Failure { summary: format!("lost track of the game: {}", e), suggestion: None })
+            },
+        };
+
+        let reason = watchdog.stop_reason();
+        let restart = watchdog::plan_restart(&item, reason, failure.is_some(), restarts, started.elapsed());
+
+        let mut report = self.finish(&tab, &item, vars, policy, failure);
+
+        match reason {
+            | Some(StopReason::MaxRuntime) => report.warnings.push(String::from("stopped after reaching max_runtime")),
+            | Some(StopReason::Idle) => report.warnings.push(String::from("stopped because it looked hung")),
+            | _ => {},
+        }
+
+        if let Some((delay, attempt)) = restart {
+            let message = format!("restarting in {}s (attempt {})", delay.as_secs(), attempt);
+            self.log.record(&format!("watchdog: `{}` {}", item.name, message));
+            report.warnings.push(message);
+
+            self.pending.push(PendingRestart { tab, item, attempt, at: Instant::now() + delay });
+        }
+
+        report
+    }
+
+    /// Record the `failure` of a game and start its `post_launch` hooks in the background.
+    /// Their warnings are reported later by `poll`.
+    fn finish(&mut self, tab: &TabConfig, item: &ItemConfig, vars: HookVars, policy: HookPolicy, failure: Option<Failure>) -> LaunchReport {
+
+        if let Some(ref failure) = failure {
+            self.log.record(&format!("`{}` failure: {}", item.name, failure));
+            self.history.push(tab, item, failure.clone());
+        }
+
+        let post_launch: Vec<String> = inherited_hooks(&self.setting.post_launch, &tab.post_launch, item)
+            .chain(item.post_launch.iter())
+            .cloned()
+            .collect();
+
+        if !post_launch.is_empty() {
+            let receiver = hook::spawn_hooks(HookStage::PostLaunch, post_launch, vars, policy, self.log.try_clone());
+            self.hooks.push((item.name.clone(), receiver));
+        }
+
+        LaunchReport { name: item.name.clone(), failure, warnings: vec![] }
+    }
+}
+
+fn instance_key(tab: &TabConfig, item: &ItemConfig) -> String {
+    format!("{}/{}", tab.name, item.name)
+}
+
+/// The global and tab hooks of a stage, unless `item` opts out of them.
+fn inherited_hooks<'a>(global: &'a [String], tab: &'a [String], item: &ItemConfig) -> impl Iterator<Item = &'a String> {
+
+    let (global, tab) = if item.inherit_hooks.unwrap_or(true) { (global, tab) } else { (&[][..], &[][..]) };
+    global.iter().chain(tab.iter())
+}
@@ -0,0 +1,208 @@
+
+use std::env;
+use std::fs::{ self, OpenOptions };
+use std::io::{ self, Write };
+use std::path::{ Path, PathBuf };
+use std::process::{ Command, Stdio };
+
+use crate::config::manifest::PREFIX_DIR_NAME;
+use crate::config::setting::SettingConfig;
+use crate::config::tab::{ TabConfig, ItemConfig };
+use crate::launch::command::{ self, LaunchCommand };
+
+/// Lists the games that were launched in a prefix, one `tab/item` per line.
+const USERS_FILE_NAME: &str = "thl-users.txt";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PrefixAction {
+    /// Delete the prefix and create it again.
+    Reset,
+    Delete,
+}
+
+/// The directory of the prefixes managed by the launcher.
+pub fn managed_dir(setting: &SettingConfig) -> PathBuf {
+
+    match setting.prefix_dir {
+        | Some(ref dir) => PathBuf::from(dir),
+        | None => env::current_dir().unwrap_or_default().join(PREFIX_DIR_NAME),
+    }
+}
+
+/// A `WINEPREFIX` managed by the launcher, named by the `wine_prefix` of a game or its tab.
+#[derive(Debug, Clone)]
+pub struct WinePrefix {
+
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl WinePrefix {
+
+    /// The managed prefix of `item`, or an error if its name is not a plain directory name.
+    pub fn for_item(setting: &SettingConfig, tab: &TabConfig, item: &ItemConfig) -> Option<Result<WinePrefix, String>> {
+
+        let name = item.wine_prefix.as_ref()
+            .or(tab.wine_prefix.as_ref())
+            .filter(|name| !name.is_empty())?;
+
+        if name.contains('/') || name == "." || name == ".." {
+            return Some(Err(format!("wine_prefix `{}` must be a plain name, not a path", name)))
+        }
+
+        Some(Ok(WinePrefix { name: name.clone(), path: managed_dir(setting).join(name) }))
+    }
+
+    pub fn exists(&self) -> bool {
+        self.path.join("system.reg").is_file()
+    }
+
+    /// Initialize the prefix with `wineboot` through the runner of `item`, in the environment of the game
+    /// so that a `WINEARCH` set there picks the architecture of the prefix.
+    pub fn create(&self, setting: &SettingConfig, tab: &TabConfig, item: &ItemConfig) -> Result<(), String> {
+
+        fs::create_dir_all(&self.path)
+            .map_err(|e| format!("failed to create {}: {}", self.path.display(), e))?;
+
+        let mut words: Vec<String> = command::resolve_runner(setting, tab, item).cloned().into_iter().collect();
+        words.extend(vec![String::from("wineboot"), String::from("--init")]);
+
+        let output = Command::new(&words[0])
+            .args(&words[1..])
+            .envs(&tab.env)
+            .envs(&item.env)
+            .env("WINEPREFIX", &self.path)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| format!("failed to run `{}`: {}", words.join(" "), e))?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let last_line = stderr.lines().last().unwrap_or("");
+            Err(format!("`{}` exited with {}: {}", words.join(" "), output.status, last_line))
+        }
+    }
+
+    /// Remember that the game `key` runs in this prefix.
+    pub fn record_user(&self, key: &str) -> io::Result<()> {
+
+        if self.users().iter().any(|user| user == key) {
+            return Ok(())
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path.join(USERS_FILE_NAME))?;
+        writeln!(file, "{}", key)
+    }
+
+    pub fn users(&self) -> Vec<String> {
+
+        fs::read_to_string(self.path.join(USERS_FILE_NAME))
+            .map(|users| users.lines().map(str::to_owned).collect())
+            .unwrap_or_default()
+    }
+
+    /// Total size of the files in the prefix, in bytes.
+    pub fn disk_usage(&self) -> u64 {
+        dir_size(&self.path)
+    }
+
+    pub fn delete(&self) -> io::Result<()> {
+
+        if self.path.exists() {
+            fs::remove_dir_all(&self.path)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Describe the prefix, one line per entry.
+    pub fn describe(&self) -> Vec<String> {
+
+        let mut lines = vec![format!("prefix : {} ({})", self.name, self.path.display())];
+
+        if self.exists() {
+            lines.push(format!("size   : {:.1} MiB", self.disk_usage() as f64 / (1024.0 * 1024.0)));
+        } else {
+            lines.push(String::from("size   : not created yet, it is created on the next launch"));
+        }
+
+        let users = self.users();
+        if users.is_empty() {
+            lines.push(String::from("used by: (no game yet)"));
+        } else {
+            lines.push(format!("used by: {}", users.join(", ")));
+        }
+
+        lines
+    }
+
+    /// Whether `command` runs in this prefix.
+    pub fn is_used_by(&self, command: &LaunchCommand) -> bool {
+        command.env.get("WINEPREFIX").is_some_and(|path| Path::new(path) == self.path)
+    }
+}
+
+/// Sum the sizes of the files under `path`. Symbolic links are not followed, as `dosdevices`
+/// links to the whole file system.
+fn dir_size(path: &Path) -> u64 {
+
+    let metadata = match fs::symlink_metadata(path) {
+        | Ok(metadata) => metadata,
+        | Err(_) => return 0,
+    };
+
+    if metadata.is_dir() {
+        fs::read_dir(path)
+            .map(|entries| entries.filter_map(Result::ok).map(|entry| dir_size(&entry.path())).sum())
+            .unwrap_or(0)
+    } else {
+        metadata.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::os::unix::fs::PermissionsExt;
+
+    use crate::utils::testing::scratch_dir;
+
+    #[test]
+    fn create_runs_wineboot_in_the_prefix() {
+
+        let dir = scratch_dir("prefix-create");
+
+        // a `wine` that records how it was called, first on PATH.
+        let bin = dir.join("bin");
+        fs::create_dir_all(&bin).unwrap();
+        let stub = bin.join("wine");
+        fs::write(&stub, "#!/bin/sh\nprintf '%s\\n' \"$*\" \"$WINEPREFIX\" \"$WINEARCH\" > \"$(dirname \"$0\")/calls\"\n").unwrap();
+        fs::set_permissions(&stub, fs::Permissions::from_mode(0o755)).unwrap();
+        let path = env::var("PATH").unwrap_or_default();
+        env::set_var("PATH", format!("{}:{}", bin.display(), path));
+
+        let setting = SettingConfig { prefix_dir: Some(dir.join("prefixes").to_string_lossy().into_owned()), ..SettingConfig::default() };
+        let tab = TabConfig { runner: Some(String::from("wine")), ..TabConfig::default() };
+        let item = ItemConfig {
+            wine_prefix: Some(String::from("th08")),
+            env: vec![(String::from("WINEARCH"), String::from("win32"))].into_iter().collect(),
+            ..ItemConfig::default()
+        };
+
+        let prefix = WinePrefix::for_item(&setting, &tab, &item).unwrap().unwrap();
+        prefix.create(&setting, &tab, &item).unwrap();
+
+        let calls = fs::read_to_string(bin.join("calls")).unwrap();
+        let expected = format!("wineboot --init\n{}\nwin32\n", dir.join("prefixes/th08").display());
+        assert_eq!(calls, expected);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
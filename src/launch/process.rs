@@ -0,0 +1,194 @@
+
+use std::fs;
+use std::io;
+use std::time::Duration;
+
+/// A snapshot of a running game, read from `/proc`.
+#[derive(Debug, Clone)]
+pub struct ProcessInfo {
+
+    pub name: String,
+    pub pid: u32,
+    pub uptime: Duration,
+    /// User and system CPU time consumed so far.
+    pub cpu_time: Option<Duration>,
+    /// Resident memory in KiB.
+    pub rss_kib: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ProcessSignal {
+    /// Ask the game to exit with SIGTERM.
+    Terminate,
+    /// Kill the game with SIGKILL.
+    Kill,
+    /// Kill the game and everything it spawned (such as `wineserver`) with SIGKILL.
+    KillGroup,
+}
+
+impl ProcessInfo {
+
+    pub fn read(name: &str, pid: u32, uptime: Duration) -> ProcessInfo {
+
+        ProcessInfo {
+            name: name.to_owned(),
+            pid, uptime,
+            cpu_time: read_cpu_time(pid),
+            rss_kib: read_rss(pid),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+
+        let cpu = match self.cpu_time {
+            | Some(cpu) => {
+                let percent = if self.uptime.as_secs_f64() > 0.0 {
+                    cpu.as_secs_f64() / self.uptime.as_secs_f64() * 100.0
+                } else {
+                    0.0
+                };
+                format!("{:.1}s ({:.0}%)", cpu.as_secs_f64(), percent)
+            },
+            | None => String::from("?"),
+        };
+        let rss = self.rss_kib
+            .map(|kib| format!("{:.1} MiB", kib as f64 / 1024.0))
+            .unwrap_or_else(|| String::from("?"));
+
+        format!("{:<20} pid {:<7} up {:<8} cpu {:<14} rss {}",
+            self.name, self.pid, format_uptime(self.uptime), cpu, rss)
+    }
+}
+
+/// Send `signal` to the game `pid`. The games are spawned as process group leaders.
+pub fn send_signal(pid: u32, signal: ProcessSignal) -> io::Result<()> {
+
+    let result = unsafe {
+        match signal {
+            | ProcessSignal::Terminate => libc::kill(pid as libc::pid_t, libc::SIGTERM),
+            | ProcessSignal::Kill      => libc::kill(pid as libc::pid_t, libc::SIGKILL),
+            | ProcessSignal::KillGroup => libc::killpg(pid as libc::pid_t, libc::SIGKILL),
+        }
+    };
+
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+pub fn read_cpu_time(pid: u32) -> Option<Duration> {
+
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // the command name may contain spaces, so count the fields after its closing parenthesis.
+    let fields: Vec<&str> = stat[stat.rfind(')')? + 1..].split_whitespace().collect();
+    // utime and stime are the 14th and 15th fields of the whole line.
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+
+    let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if ticks <= 0 {
+        return None
+    }
+
+    Some(Duration::from_millis((utime + stime) * 1000 / ticks as u64))
+}
+
+/// The CPU time used by the process group `pgid`: by its processes and by the children they have reaped,
+/// so that a game run through wrappers or a runner is measured as a whole.
+pub fn read_group_cpu_time(pgid: u32) -> Option<Duration> {
+
+    let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if ticks <= 0 {
+        return None
+    }
+
+    let mut total = None;
+    for entry in fs::read_dir("/proc").ok()?.filter_map(Result::ok) {
+        let pid = match entry.file_name().to_str().and_then(|name| name.parse::<u32>().ok()) {
+            | Some(pid) => pid,
+            | None => continue,
+        };
+        let stat = match fs::read_to_string(format!("/proc/{}/stat", pid)) {
+            | Ok(stat) => stat,
+            | Err(_) => continue,
+        };
+        let fields: Vec<&str> = match stat.rfind(')') {
+            | Some(end) => stat[end + 1..].split_whitespace().collect(),
+            | None => continue,
+        };
+
+        // pgrp is the 5th field of the whole line, utime, stime, cutime and cstime the 14th to 17th.
+        if fields.get(2).and_then(|pgrp| pgrp.parse::<u32>().ok()) != Some(pgid) {
+            continue
+        }
+        let used: u64 = fields.get(11..15).unwrap_or_default().iter()
+            .filter_map(|field| field.parse::<u64>().ok())
+            .sum();
+        total = Some(total.unwrap_or(0) + used);
+    }
+
+    total.map(|used| Duration::from_millis(used * 1000 / ticks as u64))
+}
+
+/// When `pid` started, in clock ticks after boot, or `None` once it is gone or only left as a zombie.
+pub fn read_start_time(pid: u32) -> Option<u64> {
+
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    let fields: Vec<&str> = stat[stat.rfind(')')? + 1..].split_whitespace().collect();
+    // state and starttime are the 3rd and 22nd fields of the whole line.
+    if *fields.first()? == "Z" {
+        return None
+    }
+    fields.get(19)?.parse().ok()
+}
+
+fn read_rss(pid: u32) -> Option<u64> {
+
+    let status = fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+
+    status.lines()
+        .find(|line| line.starts_with("VmRSS:"))
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|kib| kib.parse().ok())
+}
+
+fn format_uptime(uptime: Duration) -> String {
+
+    let secs = uptime.as_secs();
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::os::unix::process::CommandExt;
+    use std::process::Command;
+    use std::thread;
+
+    #[test]
+    fn measures_the_cpu_time_of_the_whole_group() {
+
+        // a wrapper that sleeps while the game it started keeps the CPU busy.
+        let mut wrapper = Command::new("sh")
+            .args(["-c", "sh -c 'while :; do :; done' & sleep 5"])
+            .process_group(0)
+            .spawn()
+            .unwrap();
+        let pgid = wrapper.id();
+
+        thread::sleep(Duration::from_millis(600));
+        let group = read_group_cpu_time(pgid).unwrap();
+        let leader = read_cpu_time(pgid).unwrap();
+
+        let _ = send_signal(pgid, ProcessSignal::KillGroup);
+        let _ = wrapper.wait();
+
+        assert!(group >= Duration::from_millis(200), "{:?}", group);
+        assert!(leader < Duration::from_millis(100), "{:?}", leader);
+        assert_eq!(read_group_cpu_time(u32::MAX), None);
+    }
+}
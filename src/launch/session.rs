@@ -0,0 +1,56 @@
+
+use std::env;
+use std::fs::{ File, OpenOptions };
+use std::io::Write;
+use std::path::Path;
+use std::time::{ SystemTime, UNIX_EPOCH };
+
+use crate::config::manifest::SESSION_LOG_NAME;
+
+/// Append-only log of what happened around each launch, kept next to the manifest.
+pub struct SessionLog {
+
+    file: Option<File>,
+}
+
+impl SessionLog {
+
+    pub fn open() -> SessionLog {
+
+        match env::current_dir() {
+            | Ok(cwd) => SessionLog::open_at(&cwd.join(SESSION_LOG_NAME)),
+            | Err(_) => SessionLog { file: None },
+        }
+    }
+
+    pub fn open_at(path: &Path) -> SessionLog {
+
+        // The launcher must keep working even if the log can not be written.
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .ok();
+
+        SessionLog { file }
+    }
+
+    /// Another handle appending to the same log, for the work done in the background.
+    pub fn try_clone(&self) -> SessionLog {
+        SessionLog { file: self.file.as_ref().and_then(|file| file.try_clone().ok()) }
+    }
+
+    pub fn record(&mut self, message: &str) {
+
+        if let Some(ref mut file) = self.file {
+
+            let stamp = SystemTime::now().duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+
+            for line in message.lines() {
+                let _ = writeln!(file, "[{}] {}", stamp, line);
+            }
+        }
+    }
+}
@@ -0,0 +1,259 @@
+
+use std::io;
+use std::mem;
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+
+use crate::config::tab::ItemConfig;
+
+// glibc types the resource of `setrlimit` with its own enum, other libcs with a plain int.
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type Resource = libc::__rlimit_resource_t;
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+type Resource = libc::c_int;
+
+/// CPU affinity, priority and resource limits applied to the game between fork and exec.
+#[derive(Debug, Clone, Default)]
+pub struct ProcessTuning {
+
+    pub affinity: Vec<usize>,
+    pub nice: Option<i32>,
+    limits: Vec<Limit>,
+    /// Settings that were ignored because they are not valid.
+    pub problems: Vec<String>,
+}
+
+/// A resource limit to set, leaving the hard limit of the launcher alone unless one is configured.
+#[derive(Debug, Clone)]
+struct Limit {
+
+    name: &'static str,
+    resource: Resource,
+    soft: Option<u64>,
+    hard: Option<u64>,
+}
+
+impl ProcessTuning {
+
+    pub fn from_item(item: &ItemConfig) -> ProcessTuning {
+
+        let mut tuning = ProcessTuning::default();
+
+        for &cpu in item.cpu_affinity.iter() {
+            if cpu < libc::CPU_SETSIZE as usize {
+                tuning.affinity.push(cpu);
+            } else {
+                tuning.problems.push(format!("cpu_affinity: {} is not a valid CPU index", cpu));
+            }
+        }
+
+        match item.nice {
+            | Some(nice) if (-20..=19).contains(&nice) => tuning.nice = Some(nice),
+            | Some(nice) => tuning.problems.push(format!("nice: {} is outside of -20..19", nice)),
+            | None => {},
+        }
+
+        let names = item.limits.keys().chain(item.hard_limits.keys());
+        for name in names {
+            match resource_by_name(name) {
+                | Some((name, resource)) => {
+                    if tuning.limits.iter().any(|limit| limit.name == name) {
+                        continue
+                    }
+                    tuning.limits.push(Limit {
+                        name, resource,
+                        soft: item.limits.get(name).cloned(),
+                        hard: item.hard_limits.get(name).cloned(),
+                    });
+                },
+                | None => tuning.problems.push(format!("limits: unknown resource `{}`", name)),
+            }
+        }
+
+        tuning
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.affinity.is_empty() && self.nice.is_none() && self.limits.is_empty()
+    }
+
+    pub fn apply(&self, command: &mut Command) {
+
+        if self.is_empty() {
+            return
+        }
+
+        // Everything is prepared before fork, since the child may only make async-signal-safe calls.
+        let affinity = if self.affinity.is_empty() {
+            None
+        } else {
+            let mut set: libc::cpu_set_t = unsafe { mem::zeroed() };
+            for &cpu in self.affinity.iter() {
+                unsafe { libc::CPU_SET(cpu, &mut set) };
+            }
+            Some(set)
+        };
+        let nice = self.nice;
+        let limits: Vec<(Resource, libc::rlimit)> = self.limits.iter()
+            .map(|limit| (limit.resource, limit.rlimit()))
+            .collect();
+
+        let pre_exec = move || {
+
+            if let Some(ref set) = affinity {
+                if unsafe { libc::sched_setaffinity(0, mem::size_of::<libc::cpu_set_t>(), set) } != 0 {
+                    return Err(io::Error::last_os_error())
+                }
+            }
+
+            if let Some(nice) = nice {
+                if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } != 0 {
+                    return Err(io::Error::last_os_error())
+                }
+            }
+
+            for (resource, limit) in limits.iter() {
+                if unsafe { libc::setrlimit(*resource, limit) } != 0 {
+                    return Err(io::Error::last_os_error())
+                }
+            }
+
+            Ok(())
+        };
+
+        unsafe { command.pre_exec(pre_exec) };
+    }
+
+    pub fn preview(&self) -> Vec<String> {
+
+        let mut lines = vec![];
+
+        if !self.affinity.is_empty() {
+            lines.push(format!("cpus   : {:?}", self.affinity));
+        }
+        if let Some(nice) = self.nice {
+            lines.push(format!("nice   : {}", nice));
+        }
+        for limit in self.limits.iter() {
+            match (limit.soft, limit.hard) {
+                | (Some(soft), Some(hard)) => lines.push(format!("limit  : {}={} (hard {})", limit.name, soft, hard)),
+                | (Some(soft), None)       => lines.push(format!("limit  : {}={}", limit.name, soft)),
+                | (None, Some(hard))       => lines.push(format!("limit  : {} hard={}", limit.name, hard)),
+                | (None, None) => {},
+            }
+        }
+        for problem in self.problems.iter() {
+            lines.push(format!("ignored: {}", problem));
+        }
+
+        lines
+    }
+}
+
+impl Limit {
+
+    /// The limit to set, starting from the current one of the launcher which the game inherits.
+    fn rlimit(&self) -> libc::rlimit {
+
+        let mut current = libc::rlimit { rlim_cur: libc::RLIM_INFINITY, rlim_max: libc::RLIM_INFINITY };
+        unsafe { libc::getrlimit(self.resource, &mut current) };
+
+        let rlim_max = self.hard.unwrap_or(current.rlim_max);
+        let rlim_cur = self.soft.unwrap_or(current.rlim_cur).min(rlim_max);
+
+        libc::rlimit { rlim_cur, rlim_max }
+    }
+}
+
+fn resource_by_name(name: &str) -> Option<(&'static str, Resource)> {
+
+    let resource = match name {
+        | "as"      => ("as", libc::RLIMIT_AS),
+        | "core"    => ("core", libc::RLIMIT_CORE),
+        | "cpu"     => ("cpu", libc::RLIMIT_CPU),
+        | "data"    => ("data", libc::RLIMIT_DATA),
+        | "fsize"   => ("fsize", libc::RLIMIT_FSIZE),
+        | "memlock" => ("memlock", libc::RLIMIT_MEMLOCK),
+        | "nofile"  => ("nofile", libc::RLIMIT_NOFILE),
+        | "nproc"   => ("nproc", libc::RLIMIT_NPROC),
+        | "stack"   => ("stack", libc::RLIMIT_STACK),
+        | _ => return None,
+    };
+
+    Some(resource)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::collections::BTreeMap;
+    use std::fs;
+
+    fn proc_field(pid: u32, file: &str, prefix: &str) -> String {
+
+        fs::read_to_string(format!("/proc/{}/{}", pid, file)).unwrap()
+            .lines()
+            .find(|line| line.starts_with(prefix))
+            .map(|line| line[prefix.len()..].trim().to_owned())
+            .unwrap_or_else(|| panic!("no `{}` in /proc/{}/{}", prefix, pid, file))
+    }
+
+    #[test]
+    fn applies_affinity_nice_and_limits() {
+
+        // pin to a CPU the test itself may run on, whatever the machine.
+        let allowed = proc_field(std::process::id(), "status", "Cpus_allowed_list:");
+        let cpu: usize = allowed.split(&[',', '-'][..]).next().unwrap().parse().unwrap();
+
+        let item = ItemConfig {
+            cpu_affinity: vec![cpu],
+            nice: Some(5),
+            limits: vec![(String::from("nofile"), 64)].into_iter().collect::<BTreeMap<_, _>>(),
+            hard_limits: vec![(String::from("core"), 0)].into_iter().collect::<BTreeMap<_, _>>(),
+            ..ItemConfig::default()
+        };
+        let tuning = ProcessTuning::from_item(&item);
+        assert!(tuning.problems.is_empty(), "{:?}", tuning.problems);
+
+        let mut command = Command::new("sleep");
+        command.arg("5");
+        tuning.apply(&mut command);
+        let mut child = command.spawn().unwrap();
+        let pid = child.id();
+
+        let cpus = proc_field(pid, "status", "Cpus_allowed_list:");
+        let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).unwrap();
+        // the fields after the parenthesized command name, where nice is the 19th field of the line.
+        let nice = stat[stat.rfind(')').unwrap() + 2..].split(' ').nth(16).unwrap().to_owned();
+        let nofile = proc_field(pid, "limits", "Max open files");
+        let core = proc_field(pid, "limits", "Max core file size");
+
+        let _ = child.kill();
+        let _ = child.wait();
+
+        assert_eq!(cpus, cpu.to_string());
+        assert_eq!(nice, "5");
+
+        // only the soft limit is lowered, the hard one stays the one of the launcher.
+        let mut current = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+        unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut current) };
+        let nofile: Vec<&str> = nofile.split_whitespace().collect();
+        assert_eq!(nofile[0], "64");
+        assert_eq!(nofile[1], current.rlim_max.to_string());
+
+        let core: Vec<&str> = core.split_whitespace().collect();
+        assert_eq!(&core[..2], &["0", "0"]);
+    }
+
+    #[test]
+    fn rejects_out_of_range_nice() {
+
+        let item = ItemConfig { nice: Some(-40), ..ItemConfig::default() };
+        let tuning = ProcessTuning::from_item(&item);
+
+        assert_eq!(tuning.nice, None);
+        assert_eq!(tuning.problems.len(), 1);
+    }
+}
@@ -0,0 +1,173 @@
+
+use std::time::{ Duration, Instant };
+
+use crate::config::tab::ItemConfig;
+use crate::launch::process::{ self, ProcessSignal };
+
+/// How long a game may take to exit after SIGTERM before its whole process group is killed.
+const STOP_GRACE: Duration = Duration::from_secs(10);
+/// The longest wait between two automatic restarts.
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+const DEFAULT_BACKOFF_SECS: u64 = 5;
+const DEFAULT_MAX_RESTARTS: u32 = 3;
+const DEFAULT_RESTART_RESET_SECS: u64 = 600;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopReason {
+    /// Stopped from the launcher by the user.
+    User,
+    /// Ran longer than `max_runtime`.
+    MaxRuntime,
+    /// Did not use any CPU time for `idle_restart` seconds, so it is considered hung.
+    Idle,
+}
+
+/// Enforces `max_runtime` and `idle_restart` on a running game.
+pub struct Watchdog {
+
+    max_runtime: Option<Duration>,
+    idle_limit: Option<Duration>,
+
+    last_cpu_time: Option<Duration>,
+    last_progress: Instant,
+    stop: Option<(StopReason, Instant)>,
+    is_escalated: bool,
+}
+
+impl Watchdog {
+
+    pub fn from_item(item: &ItemConfig) -> Watchdog {
+
+        Watchdog {
+            max_runtime: item.max_runtime.map(Duration::from_secs),
+            idle_limit : item.idle_restart.map(Duration::from_secs),
+            last_cpu_time: None,
+            last_progress: Instant::now(),
+            stop: None,
+            is_escalated: false,
+        }
+    }
+
+    /// Check the game `pid` started at `started`, and return the signal it should receive, if any.
+    pub fn inspect(&mut self, pid: u32, started: Instant) -> Option<ProcessSignal> {
+
+        if let Some((reason, since)) = self.stop {
+            // a game the user asked to terminate is only killed if the user asks again.
+            if reason != StopReason::User && !self.is_escalated && since.elapsed() > STOP_GRACE {
+                self.is_escalated = true;
+                return Some(ProcessSignal::KillGroup)
+            }
+            return None
+        }
+
+        if let Some(max_runtime) = self.max_runtime {
+            if started.elapsed() > max_runtime {
+                self.stop = Some((StopReason::MaxRuntime, Instant::now()));
+                return Some(ProcessSignal::Terminate)
+            }
+        }
+
+        if let Some(idle_limit) = self.idle_limit {
+            // the game may run below wrappers or a runner, which wait for it without using any CPU time.
+            let cpu_time = process::read_group_cpu_time(pid);
+            if cpu_time != self.last_cpu_time {
+                self.last_cpu_time = cpu_time;
+                self.last_progress = Instant::now();
+            } else if self.last_progress.elapsed() > idle_limit {
+                self.stop = Some((StopReason::Idle, Instant::now()));
+                return Some(ProcessSignal::Terminate)
+            }
+        }
+
+        None
+    }
+
+    pub fn stop_by_user(&mut self) {
+
+        if self.stop.is_none() {
+            self.stop = Some((StopReason::User, Instant::now()));
+        }
+    }
+
+    pub fn stop_reason(&self) -> Option<StopReason> {
+        self.stop.map(|(reason, _)| reason)
+    }
+}
+
+/// The restart to schedule after a game exited, as the delay and the attempt number.
+///
+/// `restarts` counts the restarts in a row that led to the run, which lasted `uptime`. A run longer
+/// than `restart_reset_after` was a healthy one, so the restarts before it are not counted anymore.
+pub fn plan_restart(item: &ItemConfig, reason: Option<StopReason>, is_crash: bool, restarts: u32, uptime: Duration) -> Option<(Duration, u32)> {
+
+    let should_restart = match reason {
+        | Some(StopReason::Idle) => true,
+        | Some(StopReason::User)
+        | Some(StopReason::MaxRuntime) => false,
+        | None => is_crash && item.restart_on_crash.unwrap_or(false),
+    };
+
+    let restarts = if uptime >= Duration::from_secs(item.restart_reset_after.unwrap_or(DEFAULT_RESTART_RESET_SECS)) {
+        0
+    } else {
+        restarts
+    };
+
+    if !should_restart || restarts >= item.max_restarts.unwrap_or(DEFAULT_MAX_RESTARTS) {
+        return None
+    }
+
+    let base = Duration::from_secs(item.restart_backoff.unwrap_or(DEFAULT_BACKOFF_SECS));
+    let delay = base.checked_mul(1 << restarts.min(16))
+        .unwrap_or(MAX_BACKOFF)
+        .min(MAX_BACKOFF);
+
+    Some((delay, restarts + 1))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const SHORT: Duration = Duration::from_secs(30);
+
+    fn item() -> ItemConfig {
+        ItemConfig { restart_on_crash: Some(true), ..ItemConfig::default() }
+    }
+
+    #[test]
+    fn restarts_crashed_and_hung_games_only() {
+
+        assert_eq!(plan_restart(&item(), None, true, 0, SHORT), Some((Duration::from_secs(5), 1)));
+        assert_eq!(plan_restart(&item(), None, false, 0, SHORT), None);
+        assert_eq!(plan_restart(&ItemConfig::default(), None, true, 0, SHORT), None);
+        // hung games are restarted without `restart_on_crash`.
+        assert_eq!(plan_restart(&ItemConfig::default(), Some(StopReason::Idle), false, 0, SHORT), Some((Duration::from_secs(5), 1)));
+        assert_eq!(plan_restart(&item(), Some(StopReason::User), true, 0, SHORT), None);
+        assert_eq!(plan_restart(&item(), Some(StopReason::MaxRuntime), true, 0, SHORT), None);
+    }
+
+    #[test]
+    fn backs_off_until_max_restarts() {
+
+        let item = ItemConfig { restart_backoff: Some(100), max_restarts: Some(4), ..item() };
+
+        assert_eq!(plan_restart(&item, None, true, 0, SHORT), Some((Duration::from_secs(100), 1)));
+        assert_eq!(plan_restart(&item, None, true, 1, SHORT), Some((Duration::from_secs(200), 2)));
+        assert_eq!(plan_restart(&item, None, true, 3, SHORT), Some((MAX_BACKOFF, 4)));
+        assert_eq!(plan_restart(&item, None, true, 4, SHORT), None);
+        assert_eq!(plan_restart(&ItemConfig { max_restarts: Some(0), ..item }, None, true, 0, SHORT), None);
+    }
+
+    #[test]
+    fn forgets_the_restarts_before_a_long_run() {
+
+        assert_eq!(plan_restart(&item(), None, true, 3, Duration::from_secs(599)), None);
+        assert_eq!(plan_restart(&item(), None, true, 3, Duration::from_secs(600)), Some((Duration::from_secs(5), 1)));
+
+        let item = ItemConfig { restart_reset_after: Some(60), ..item() };
+        assert_eq!(plan_restart(&item, None, true, 3, Duration::from_secs(61)), Some((Duration::from_secs(5), 1)));
+    }
+}
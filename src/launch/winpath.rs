@@ -0,0 +1,218 @@
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::config::setting::SettingConfig;
+use crate::config::tab::ItemConfig;
+
+/// Translates the Windows paths of a shared manifest to local ones, following the `path_map` setting.
+#[derive(Debug, Clone, Default)]
+pub struct PathMap {
+
+    /// Windows prefixes with backslashes and in lowercase, longest first.
+    rules: Vec<(String, String)>,
+    /// The same rules with the Windows prefixes as written, longest local prefix first.
+    reverse: Vec<(String, String)>,
+}
+
+impl PathMap {
+
+    pub fn from_setting(setting: &SettingConfig) -> PathMap {
+        PathMap::new(&setting.path_map)
+    }
+
+    pub fn new(map: &BTreeMap<String, String>) -> PathMap {
+
+        let mut rules: Vec<(String, String)> = map.iter()
+            .map(|(windows, unix)| (normalize(windows).trim_end_matches('\\').to_owned(), unix.trim_end_matches('/').to_owned()))
+            .collect();
+        rules.sort_by_key(|rule| std::cmp::Reverse(rule.0.len()));
+
+        let mut reverse: Vec<(String, String)> = map.iter()
+            .map(|(windows, unix)| (windows.replace('/', "\\").trim_end_matches('\\').to_owned(), unix.trim_end_matches('/').to_owned()))
+            .collect();
+        reverse.sort_by_key(|rule| std::cmp::Reverse(rule.1.len()));
+
+        PathMap { rules, reverse }
+    }
+
+    /// Convert a Windows path such as `D:\Games\th08.exe` with the first matching rule, or
+    /// through the `Z:` drive of Wine. Other paths are returned unchanged.
+    pub fn to_unix(&self, path: &str) -> String {
+
+        if !is_windows_path(path) {
+            return path.to_owned()
+        }
+
+        let normalized = normalize(path);
+
+        for (windows, unix) in self.rules.iter() {
+            let rest = match (normalized.get(..windows.len()), path.get(windows.len()..)) {
+                | (Some(prefix), Some(rest)) if prefix == windows => rest,
+                | _ => continue,
+            };
+
+            // only match whole components, `D:\Game` is not a prefix of `D:\Games`.
+            if rest.is_empty() || rest.starts_with('\\') || rest.starts_with('/') {
+                return format!("{}{}", unix, rest.replace('\\', "/"))
+            }
+        }
+
+        if normalized.starts_with("z:\\") {
+            return path[2..].replace('\\', "/")
+        }
+
+        path.to_owned()
+    }
+
+    /// Convert a local absolute path back to the Windows path of the first matching rule, or to
+    /// the `Z:` drive of Wine, so it can be written to a shared manifest. Other paths are returned unchanged.
+    pub fn to_windows(&self, path: &str) -> String {
+
+        if !path.starts_with('/') {
+            return path.to_owned()
+        }
+
+        for (windows, unix) in self.reverse.iter() {
+            let rest = match path.strip_prefix(unix.as_str()) {
+                | Some(rest) => rest,
+                | None => continue,
+            };
+
+            if rest.is_empty() || rest.starts_with('/') {
+                return format!("{}{}", windows, rest.replace('/', "\\"))
+            }
+        }
+
+        format!("Z:{}", path.replace('/', "\\"))
+    }
+
+    /// Translate the paths of `item` that are read by the launcher itself.
+    pub fn localize(&self, item: &ItemConfig) -> ItemConfig {
+
+        let mut item = item.clone();
+        item.path = self.to_unix(&item.path);
+        item.cwd = item.cwd.map(|cwd| self.to_unix(&cwd));
+        if let Some(ref mut thcrap) = item.thcrap {
+            thcrap.dir = self.to_unix(&thcrap.dir);
+        }
+        item
+    }
+}
+
+/// The path of an existing local file or directory as seen by Windows programs under Wine.
+pub fn to_wine(path: &str) -> Option<String> {
+
+    if path.starts_with('/') && Path::new(path).exists() {
+        Some(format!("Z:{}", path.replace('/', "\\")))
+    } else {
+        None
+    }
+}
+
+/// Whether `path` starts with a drive letter or is a UNC path.
+pub fn is_windows_path(path: &str) -> bool {
+
+    let bytes = path.as_bytes();
+    let has_drive = bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+        && (bytes.len() == 2 || bytes[2] == b'\\' || bytes[2] == b'/');
+
+    has_drive || path.starts_with("\\\\")
+}
+
+/// Windows paths are case insensitive. Only ASCII is folded, so byte offsets stay valid for `path`.
+fn normalize(path: &str) -> String {
+    path.replace('/', "\\").to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::utils::testing::scratch_dir;
+
+    use std::fs;
+
+    fn map(rules: &[(&str, &str)]) -> PathMap {
+        PathMap::new(&rules.iter().map(|(windows, unix)| (windows.to_string(), unix.to_string())).collect())
+    }
+
+    #[test]
+    fn the_longest_prefix_wins() {
+
+        let map = map(&[(r"D:\Games", "/mnt/games"), (r"D:\Games\Touhou\", "/home/reimu/touhou/"), (r"D:\", "/mnt/d")]);
+
+        assert_eq!(map.to_unix(r"D:\Games\Touhou\th08\th08.exe"), "/home/reimu/touhou/th08/th08.exe");
+        assert_eq!(map.to_unix(r"D:\Games\th17\th17.exe"), "/mnt/games/th17/th17.exe");
+        assert_eq!(map.to_unix(r"D:\Other\th06.exe"), "/mnt/d/Other/th06.exe");
+        assert_eq!(map.to_unix(r"D:\Games"), "/mnt/games");
+        // whole components only.
+        assert_eq!(map.to_unix(r"D:\GamesOld\th07.exe"), "/mnt/d/GamesOld/th07.exe");
+    }
+
+    #[test]
+    fn folds_the_case_and_the_separators_of_windows_paths() {
+
+        let map = map(&[(r"d:\games", "/mnt/games")]);
+
+        assert_eq!(map.to_unix(r"D:\GAMES\Th08\th08.exe"), "/mnt/games/Th08/th08.exe");
+        assert_eq!(map.to_unix("D:/Games/th08/th08.exe"), "/mnt/games/th08/th08.exe");
+        assert_eq!(map.to_unix(r"D:/Games\th08/th08.exe"), "/mnt/games/th08/th08.exe");
+    }
+
+    #[test]
+    fn converts_local_paths_back_with_the_longest_rule() {
+
+        let map = map(&[(r"D:\Games", "/mnt/games"), (r"E:/Touhou/", "/mnt/games/touhou/")]);
+
+        assert_eq!(map.to_windows("/mnt/games/touhou/th08/th08.exe"), r"E:\Touhou\th08\th08.exe");
+        assert_eq!(map.to_windows("/mnt/games/th17/th17.exe"), r"D:\Games\th17\th17.exe");
+        assert_eq!(map.to_windows("/mnt/gamesold/th07.exe"), r"Z:\mnt\gamesold\th07.exe");
+        assert_eq!(map.to_windows("th08.exe"), "th08.exe");
+
+        for path in [r"D:\Games\th17\th17.exe", r"Z:\home\reimu\th06.exe"].iter() {
+            assert_eq!(map.to_windows(&map.to_unix(path)), *path);
+        }
+    }
+
+    #[test]
+    fn falls_back_to_the_z_drive() {
+
+        let map = map(&[(r"D:\Games", "/mnt/games")]);
+
+        assert_eq!(map.to_unix(r"Z:\home\reimu\th08.exe"), "/home/reimu/th08.exe");
+        assert_eq!(map.to_unix(r"z:\home\reimu\th08.exe"), "/home/reimu/th08.exe");
+        assert_eq!(map.to_unix(r"C:\Games\th08.exe"), r"C:\Games\th08.exe");
+        assert_eq!(map.to_unix("/home/reimu/th08.exe"), "/home/reimu/th08.exe");
+        assert_eq!(map.to_unix("th08.exe"), "th08.exe");
+    }
+
+    #[test]
+    fn recognizes_windows_paths() {
+
+        assert!(is_windows_path(r"C:\Games"));
+        assert!(is_windows_path("c:/Games"));
+        assert!(is_windows_path("D:"));
+        assert!(is_windows_path(r"\\server\share\th08.exe"));
+        assert!(!is_windows_path("C:Games"));
+        assert!(!is_windows_path("/home/reimu"));
+        assert!(!is_windows_path("-w"));
+        assert!(!is_windows_path(""));
+    }
+
+    #[test]
+    fn only_gives_existing_absolute_paths_to_wine() {
+
+        let dir = scratch_dir("winpath-to-wine");
+        let replay = dir.join("th08_01.rpy");
+        fs::write(&replay, b"").unwrap();
+        let replay = replay.to_str().unwrap();
+
+        assert_eq!(to_wine(replay), Some(format!("Z:{}", replay.replace('/', "\\"))));
+        assert_eq!(to_wine("th08_01.rpy"), None);
+        assert_eq!(to_wine("-w"), None);
+        assert_eq!(to_wine(&format!("{}/missing.rpy", dir.display())), None);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
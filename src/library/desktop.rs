@@ -0,0 +1,404 @@
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{ Path, PathBuf };
+use std::ptr;
+
+use crate::config::tab::{ TabConfig, ItemConfig };
+use crate::library::scan::Discovery;
+use crate::library::sha256;
+use crate::library::titles;
+
+/// The key marking the entries written by `write_entry`, holding the `<tab>/<game>` they run.
+const ID_KEY: &str = "X-TH-Launcher-Id";
+const FILE_PREFIX: &str = "th-launcher-";
+
+/// The programs recognized as the runner of a game in an `Exec` line.
+const KNOWN_RUNNERS: &[&str] = &["wine", "wine64", "proton", "umu-run"];
+
+/// Where the desktop entries of the user are, `$XDG_DATA_HOME/applications`.
+pub fn applications_dir() -> Option<PathBuf> {
+
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))?;
+    Some(data_home.join("applications"))
+}
+
+/// The directories searched by `import` when none is given.
+pub fn default_import_dirs() -> Vec<PathBuf> {
+
+    let mut dirs: Vec<PathBuf> = applications_dir().into_iter().collect();
+    if let Some(home) = env::var_os("HOME") {
+        dirs.push(PathBuf::from(home).join("Desktop"));
+    }
+    dirs.push(PathBuf::from("/usr/share/applications"));
+    dirs
+}
+
+/// The selector of `item`, as accepted by `th-launcher run`. The games sharing the name of an earlier
+/// one in their tab are told apart by their rank, such as `Mainline/th08#2`.
+pub fn entry_id(tab: &TabConfig, item: &ItemConfig) -> String {
+
+    let rank = tab.items.iter()
+        .position(|other| ptr::eq(other, item))
+        .map_or(1, |index| tab.items[..index].iter().filter(|other| other.name == item.name).count() + 1);
+
+    match rank {
+        | 1 => format!("{}/{}", tab.name, item.name),
+        | rank => format!("{}/{}#{}", tab.name, item.name, rank),
+    }
+}
+
+/// Write the desktop entry running `item` through `launcher`, started in `manifest_dir`
+/// so the launcher finds its manifest.
+pub fn write_entry(dir: &Path, tab: &TabConfig, item: &ItemConfig, launcher: &Path, manifest_dir: &Path) -> io::Result<PathBuf> {
+
+    let id = entry_id(tab, item);
+    let exec = [launcher.to_string_lossy().into_owned(), String::from("run"), id.clone()].iter()
+        .map(|arg| exec_quote(arg))
+        .collect::<Vec<String>>()
+        .join(" ");
+    let comment = titles::identify(item).map_or_else(|| tab.name.clone(), |title| title.subtitle());
+
+    let content = [
+        String::from("[Desktop Entry]"),
+        String::from("Type=Application"),
+        format!("Name={}", escape_value(&item.name)),
+        format!("Comment={}", escape_value(&comment)),
+        format!("Exec={}", escape_value(&exec)),
+        format!("Path={}", escape_value(&manifest_dir.to_string_lossy())),
+        String::from("Icon=applications-games"),
+        String::from("Terminal=false"),
+        String::from("Categories=Game;"),
+        format!("{}={}", ID_KEY, escape_value(&id)),
+    ].join("\n") + "\n";
+
+    fs::create_dir_all(dir)?;
+    let path = dir.join(file_name(&id));
+    fs::write(&path, content)?;
+    Ok(path)
+}
+
+/// The entries of `dir` written by `write_entry`, with the selector of their game.
+pub fn generated_entries(dir: &Path) -> Vec<(PathBuf, String)> {
+
+    let entries = match fs::read_dir(dir) {
+        | Ok(entries) => entries,
+        | Err(_) => return vec![],
+    };
+
+    let mut generated: Vec<(PathBuf, String)> = entries.filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.file_name().unwrap_or_default().to_string_lossy().starts_with(FILE_PREFIX))
+        .filter_map(|path| {
+            let id = read_entry(&path)?.remove(ID_KEY)?;
+            Some((path, id))
+        })
+        .collect();
+    generated.sort();
+    generated
+}
+
+/// The games started by the desktop entries of `dirs`, other than the ones written by `write_entry`.
+pub fn import(dirs: &[PathBuf]) -> Vec<Discovery> {
+
+    let mut found = vec![];
+
+    for dir in dirs.iter() {
+        let entries = match fs::read_dir(dir) {
+            | Ok(entries) => entries,
+            | Err(_) => continue,
+        };
+
+        let mut paths: Vec<PathBuf> = entries.filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "desktop"))
+            .collect();
+        paths.sort();
+
+        found.extend(paths.iter().filter_map(|path| read_entry(path)).filter_map(discover));
+    }
+
+    found
+}
+
+/// The game run by the `Exec` line of an entry, such as `env LANG=ja_JP.UTF-8 wine "/games/th08/th08.exe"`.
+fn discover(mut entry: BTreeMap<String, String>) -> Option<Discovery> {
+
+    if entry.contains_key(ID_KEY) {
+        return None
+    }
+
+    let mut words = split_exec(&entry.remove("Exec")?);
+
+    let mut env = BTreeMap::new();
+    if words.first().is_some_and(|word| word == "env") {
+        words.remove(0);
+    }
+    while let Some(split) = words.first().and_then(|word| word.find('=')) {
+        let word = words.remove(0);
+        env.insert(word[..split].to_owned(), word[split + 1..].to_owned());
+    }
+
+    let (index, title) = words.iter().enumerate().find_map(|(index, word)| {
+        // Windows paths of the games use backslashes.
+        let file_name = word.rsplit(['/', '\\']).next()?;
+        titles::by_executable(file_name).map(|title| (index, title))
+    })?;
+
+    let mut before: Vec<String> = words.drain(..=index).collect();
+    let path = before.pop().unwrap_or_default();
+    let (runner, wrappers) = split_runner(before);
+
+    let item = ItemConfig {
+        name: entry.remove("Name").filter(|name| !name.is_empty()).unwrap_or_else(|| title.display_name()),
+        path,
+        runner,
+        args: words,
+        cwd: entry.remove("Path").filter(|cwd| !cwd.is_empty()),
+        wrappers: if wrappers.is_empty() { vec![] } else { vec![wrappers.join(" ")] },
+        env,
+        ..ItemConfig::default()
+    };
+
+    Some(Discovery { group: title.group, title, item })
+}
+
+/// Tell the runner of a game from the programs ahead of it, such as `gamemoderun wine start /unix`.
+///
+/// `wine` alone becomes the runner, the programs before it wrappers. Runners taking arguments of
+/// their own, such as `proton run`, are kept whole as a wrapper and the game is run directly.
+fn split_runner(mut before: Vec<String>) -> (Option<String>, Vec<String>) {
+
+    let is_runner = |word: &String, runners: &[&str]| {
+        Path::new(word).file_name().is_some_and(|name| runners.iter().any(|runner| name == *runner))
+    };
+
+    let index = match before.iter().rposition(|word| is_runner(word, KNOWN_RUNNERS)) {
+        | Some(index) => index,
+        | None => return (None, before),
+    };
+
+    // `wine start /unix <path>` opens a Unix path, which is what `wine <path>` does as well.
+    let is_wine = is_runner(&before[index], &["wine", "wine64"]);
+    let is_start = before.get(index + 1).is_some_and(|word| word.eq_ignore_ascii_case("start"))
+        && before[index + 2..].iter().all(|word| word.starts_with('/'));
+    if is_wine && is_start {
+        before.truncate(index + 1);
+    }
+
+    if is_wine && index + 1 == before.len() {
+        let runner = before.pop();
+        (runner, before)
+    } else {
+        (Some(String::new()), before)
+    }
+}
+
+/// The keys of the `[Desktop Entry]` group of the file at `path`, with their values unescaped.
+fn read_entry(path: &Path) -> Option<BTreeMap<String, String>> {
+
+    let content = fs::read_to_string(path).ok()?;
+    let mut entry = BTreeMap::new();
+    let mut is_main_group = false;
+
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            is_main_group = line == "[Desktop Entry]";
+        } else if is_main_group && !line.starts_with('#') {
+            if let Some(split) = line.find('=') {
+                // localized keys such as `Name[ja]` are left out.
+                let key = line[..split].trim();
+                if !key.contains('[') {
+                    entry.insert(key.to_owned(), unescape_value(line[split + 1..].trim()));
+                }
+            }
+        }
+    }
+
+    if entry.get("Type").is_some_and(|kind| kind == "Application") {
+        Some(entry)
+    } else {
+        None
+    }
+}
+
+fn escape_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\n', "\\n").replace('\t', "\\t")
+}
+
+fn unescape_value(value: &str) -> String {
+
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            unescaped.push(ch);
+            continue
+        }
+        match chars.next() {
+            | Some('s') => unescaped.push(' '),
+            | Some('n') => unescaped.push('\n'),
+            | Some('t') => unescaped.push('\t'),
+            | Some('r') => unescaped.push('\r'),
+            | Some(other) => unescaped.push(other),
+            | None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Quote an argument of `Exec`, as the desktop entry specification requires for reserved characters.
+fn exec_quote(arg: &str) -> String {
+
+    const RESERVED: &str = " \t\n\"'\\><~|&;$*?#()`";
+
+    let arg = arg.replace('%', "%%");
+    if !arg.is_empty() && !arg.contains(|ch| RESERVED.contains(ch)) {
+        return arg
+    }
+
+    let mut quoted = String::from("\"");
+    for ch in arg.chars() {
+        if "\"`$\\".contains(ch) {
+            quoted.push('\\');
+        }
+        quoted.push(ch);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Split an unescaped `Exec` value into words, leaving out the field codes such as `%f`.
+fn split_exec(exec: &str) -> Vec<String> {
+
+    let mut words = vec![];
+    let mut word: Option<String> = None;
+    let mut chars = exec.chars();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            | '"' => {
+                let word = word.get_or_insert_with(String::new);
+                while let Some(ch) = chars.next() {
+                    match ch {
+                        | '"' => break,
+                        | '\\' => word.extend(chars.next()),
+                        | ch => word.push(ch),
+                    }
+                }
+            },
+            | '%' => match chars.next() {
+                | Some('%') => word.get_or_insert_with(String::new).push('%'),
+                | _ => {},
+            },
+            | ch if ch.is_whitespace() => words.extend(word.take()),
+            | ch => word.get_or_insert_with(String::new).push(ch),
+        }
+    }
+    words.extend(word);
+    words
+}
+
+/// The file of the entry of `id`, readable from its slug and kept apart from similar ids by a hash.
+fn file_name(id: &str) -> String {
+
+    format!("{}{}-{}.desktop", FILE_PREFIX, slug(id), &sha256::hex_digest(id.as_bytes())[..8])
+}
+
+/// A file name part made of the letters and digits of `id`.
+fn slug(id: &str) -> String {
+
+    let mut slug = String::new();
+    for ch in id.chars().flat_map(char::to_lowercase) {
+        if ch.is_alphanumeric() {
+            slug.push(ch);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::utils::testing::scratch_dir;
+
+    fn discover_exec(exec: &str) -> Option<ItemConfig> {
+
+        let entry = vec![
+            (String::from("Type"), String::from("Application")),
+            (String::from("Name"), String::from("Game")),
+            (String::from("Exec"), exec.to_owned()),
+        ];
+        discover(entry.into_iter().collect()).map(|discovery| discovery.item)
+    }
+
+    #[test]
+    fn recognizes_runners() {
+
+        let item = discover_exec("wine start /unix /games/th08/th08.exe").unwrap();
+        assert_eq!((item.runner.as_deref(), item.path.as_str()), (Some("wine"), "/games/th08/th08.exe"));
+        assert!(item.wrappers.is_empty());
+
+        let item = discover_exec("env WINEPREFIX=/pfx LANG=ja_JP.UTF-8 gamemoderun /usr/bin/wine64 \"/games/th 07/th07.exe\" -w").unwrap();
+        assert_eq!(item.runner.as_deref(), Some("/usr/bin/wine64"));
+        assert_eq!(item.wrappers, vec![String::from("gamemoderun")]);
+        assert_eq!(item.path, "/games/th 07/th07.exe");
+        assert_eq!(item.args, vec![String::from("-w")]);
+        assert_eq!(item.env.get("WINEPREFIX").map(String::as_str), Some("/pfx"));
+
+        // runners with arguments of their own stay whole, and the game is not run through another runner.
+        let item = discover_exec("/opt/proton/proton run /games/th10/th10.exe").unwrap();
+        assert_eq!(item.runner.as_deref(), Some(""));
+        assert_eq!(item.wrappers, vec![String::from("/opt/proton/proton run")]);
+
+        let item = discover_exec("umu-run /games/th11/th11.exe").unwrap();
+        assert_eq!((item.runner.as_deref(), item.wrappers.clone()), (Some(""), vec![String::from("umu-run")]));
+
+        let item = discover_exec("gamemoderun /games/th12/th12.exe %U").unwrap();
+        assert_eq!((item.runner, item.wrappers), (None, vec![String::from("gamemoderun")]));
+
+        assert!(discover_exec("wine notepad.exe").is_none());
+    }
+
+    #[test]
+    fn entries_of_similar_games_do_not_collide() {
+
+        let dir = scratch_dir("desktop-entries");
+        let tabs = [
+            TabConfig { name: String::from("Main line"), items: vec![ItemConfig { name: String::from("th08"), ..ItemConfig::default() }], ..TabConfig::default() },
+            TabConfig { name: String::from("main-line"), items: vec![ItemConfig { name: String::from("th08"), ..ItemConfig::default() }; 2], ..TabConfig::default() },
+        ];
+
+        let mut ids = vec![];
+        for tab in tabs.iter() {
+            for item in tab.items.iter() {
+                write_entry(&dir, tab, item, Path::new("/usr/bin/th-launcher"), Path::new("/home/user")).unwrap();
+                ids.push(entry_id(tab, item));
+            }
+        }
+
+        assert_eq!(ids, vec!["Main line/th08", "main-line/th08", "main-line/th08#2"]);
+        let written: Vec<String> = generated_entries(&dir).into_iter().map(|(_, id)| id).collect();
+        assert_eq!(written.len(), 3);
+        for id in ids.iter() {
+            assert!(written.contains(id), "no entry for {}", id);
+        }
+
+        // the entries run their own game, and are not imported back as new games.
+        let entry = read_entry(&dir.join(file_name("main-line/th08#2"))).unwrap();
+        assert_eq!(entry.get("Exec").map(String::as_str), Some("/usr/bin/th-launcher run \"main-line/th08#2\""));
+        assert!(import(std::slice::from_ref(&dir)).is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,208 @@
+
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{ Path, PathBuf };
+use std::sync::mpsc::{ self, Receiver };
+use std::thread;
+
+use crate::config::setting::SettingConfig;
+use crate::config::tab::{ TabConfig, ItemConfig };
+use crate::launch::command;
+use crate::launch::winpath::PathMap;
+
+/// How deep below each searched directory `relink_candidates` looks.
+const RELINK_DEPTH: usize = 3;
+const MAX_RELINK_CANDIDATES: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathStatus {
+    Ready,
+    /// Nothing is at the path, such as when its drive is not mounted.
+    Missing,
+    /// The path exists, but can not be executed without a runner.
+    NotExecutable,
+}
+
+/// The status of every game of some tabs, checked on a background thread since `stat` on a stale
+/// network mount can block for minutes.
+pub struct HealthCheck {
+
+    receiver: Receiver<Vec<Vec<PathStatus>>>,
+}
+
+impl HealthCheck {
+
+    pub fn start(setting: SettingConfig, tabs: Vec<TabConfig>) -> HealthCheck {
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let health = tabs.iter()
+                .map(|tab| tab.items.iter().map(|item| check(&setting, tab, item)).collect())
+                .collect();
+            let _ = sender.send(health);
+        });
+
+        HealthCheck { receiver }
+    }
+
+    /// The status of the games, per tab and item, once the check is done.
+    pub fn poll(&self) -> Option<Vec<Vec<PathStatus>>> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+/// Check that the game `item` of `tab` can be started from its path.
+pub fn check(setting: &SettingConfig, tab: &TabConfig, item: &ItemConfig) -> PathStatus {
+
+    let item = PathMap::from_setting(setting).localize(&item.resolve_variant());
+
+    let metadata = match fs::metadata(locate(&item.path)) {
+        | Ok(metadata) => metadata,
+        | Err(_) => return PathStatus::Missing,
+    };
+
+    // a runner such as `wine` reads the game, the game itself does not need to be executable.
+    let is_direct = item.thcrap.is_none() && command::resolve_runner(setting, tab, &item).is_none();
+
+    if !metadata.is_file() || (is_direct && metadata.permissions().mode() & 0o111 == 0) {
+        PathStatus::NotExecutable
+    } else {
+        PathStatus::Ready
+    }
+}
+
+/// The file started for `path`, searched in `PATH` when it is a bare command such as `steam`.
+pub fn locate(path: &str) -> PathBuf {
+
+    if path.contains('/') {
+        return PathBuf::from(path)
+    }
+
+    env::var_os("PATH")
+        .and_then(|dirs| env::split_paths(&dirs).map(|dir| dir.join(path)).find(|path| path.is_file()))
+        .unwrap_or_else(|| PathBuf::from(path))
+}
+
+/// Look for a file with the name of `path` around its closest existing directory and under `roots`.
+pub fn relink_candidates(path: &str, roots: &[PathBuf]) -> Vec<PathBuf> {
+
+    // Windows paths of shared manifests use backslashes.
+    let file_name = match path.rsplit(['/', '\\']).next().filter(|name| !name.is_empty()) {
+        | Some(name) => name.to_lowercase(),
+        | None => return vec![],
+    };
+
+    let mut dirs: Vec<PathBuf> = vec![];
+    if let Some(existing) = Path::new(path).ancestors().skip(1).find(|dir| dir.is_dir()) {
+        // the parent of the closest directory also covers renamed siblings, such as `th08` to `th08-en`.
+        dirs.extend(existing.parent().filter(|parent| parent.parent().is_some()).map(Path::to_path_buf));
+        dirs.push(existing.to_path_buf());
+    }
+    dirs.extend(roots.iter().cloned());
+
+    let mut found = vec![];
+    for dir in dirs.iter() {
+        search(dir, &file_name, 0, &mut found);
+    }
+
+    found.sort();
+    found.dedup();
+    found.retain(|candidate| candidate.as_path() != Path::new(path));
+    found.truncate(MAX_RELINK_CANDIDATES);
+    found
+}
+
+fn search(dir: &Path, file_name: &str, depth: usize, found: &mut Vec<PathBuf>) {
+
+    let entries = match fs::read_dir(dir) {
+        | Ok(entries) => entries,
+        | Err(_) => return,
+    };
+
+    for entry in entries.filter_map(Result::ok) {
+        let file_type = match entry.file_type() {
+            | Ok(file_type) => file_type,
+            | Err(_) => continue,
+        };
+
+        if file_type.is_dir() && depth < RELINK_DEPTH {
+            search(&entry.path(), file_name, depth + 1, found);
+        } else if file_type.is_file() && entry.file_name().to_string_lossy().to_lowercase() == file_name {
+            found.push(entry.path());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::utils::testing::scratch_dir;
+
+    fn touch(path: &Path, mode: u32) {
+
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "").unwrap();
+        fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    fn item(path: &Path) -> ItemConfig {
+        ItemConfig { name: String::from("th08"), path: path.to_string_lossy().into_owned(), ..ItemConfig::default() }
+    }
+
+    #[test]
+    fn classifies_the_paths_of_the_games() {
+
+        let dir = scratch_dir("health-check");
+        touch(&dir.join("th08/th08.exe"), 0o644);
+        touch(&dir.join("th08/run.sh"), 0o755);
+
+        let setting = SettingConfig::default();
+        let tab = TabConfig::default();
+        let wine = TabConfig { runner: Some(String::from("wine")), ..TabConfig::default() };
+
+        assert_eq!(check(&setting, &tab, &item(&dir.join("th08/run.sh"))), PathStatus::Ready);
+        assert_eq!(check(&setting, &tab, &item(&dir.join("th08/th08.exe"))), PathStatus::NotExecutable);
+        assert_eq!(check(&setting, &wine, &item(&dir.join("th08/th08.exe"))), PathStatus::Ready);
+        assert_eq!(check(&setting, &wine, &item(&dir.join("th08"))), PathStatus::NotExecutable);
+        assert_eq!(check(&setting, &wine, &item(&dir.join("th07/th07.exe"))), PathStatus::Missing);
+
+        // the path map applies before the check.
+        let mut mapped = SettingConfig::default();
+        mapped.path_map.insert(String::from(r"D:\Games"), dir.to_string_lossy().into_owned());
+        let windows = ItemConfig { path: String::from(r"D:\Games\th08\th08.exe"), ..item(&dir) };
+        assert_eq!(check(&mapped, &wine, &windows), PathStatus::Ready);
+        assert_eq!(check(&setting, &wine, &windows), PathStatus::Missing);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn finds_moved_games_nearby_and_under_the_roots() {
+
+        let dir = scratch_dir("relink");
+        touch(&dir.join("games/th08-en/TH08.exe"), 0o644);
+        touch(&dir.join("games/th08/backup/th08.exe"), 0o644);
+        touch(&dir.join("games/th08/custom.exe"), 0o644);
+        touch(&dir.join("usb/touhou/th08/th08.exe"), 0o644);
+        touch(&dir.join("usb/a/b/c/d/th08.exe"), 0o644);
+
+        let path = dir.join("games/th08/th08.exe");
+        let candidates = relink_candidates(&path.to_string_lossy(), &[dir.join("usb")]);
+        assert_eq!(candidates, vec![
+            dir.join("games/th08/backup/th08.exe"),
+            dir.join("games/th08-en/TH08.exe"),
+            dir.join("usb/touhou/th08/th08.exe"),
+        ]);
+
+        // the file itself is not offered again.
+        touch(&path, 0o644);
+        assert!(!relink_candidates(&path.to_string_lossy(), &[]).contains(&path));
+
+        assert!(relink_candidates("", &[dir.join("usb")]).is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,380 @@
+
+use std::env;
+use std::fs;
+use std::io::{ self, BufRead, BufReader, Read };
+use std::os::unix::ffi::OsStrExt;
+use std::path::{ Path, PathBuf };
+use std::process::{ Child, Command, ExitStatus, Output, Stdio };
+use std::sync::mpsc::{ self, Receiver, Sender };
+use std::thread;
+
+use encoding_rs::SHIFT_JIS;
+
+use crate::config::manifest::LIBRARY_DIR_NAME;
+use crate::config::setting::SettingConfig;
+use crate::library::titles::{ self, TouhouTitle };
+
+/// How deep below the extracted directory the executable of the game is looked for.
+const MAX_EXE_DEPTH: usize = 4;
+/// Words of the executables that are not the game, such as its setup or its config tool.
+const AUXILIARY_WORDS: &[&str] = &["setup", "install", "uninst", "config", "custom", "vpatch", "thcrap", "update", "crash"];
+
+/// The directory the archives are installed into.
+pub fn library_dir(setting: &SettingConfig) -> PathBuf {
+
+    match setting.library_dir {
+        | Some(ref dir) => PathBuf::from(dir),
+        | None => env::current_dir().unwrap_or_default().join(LIBRARY_DIR_NAME),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveKind {
+    Zip,
+    SevenZip,
+    Rar,
+}
+
+impl ArchiveKind {
+
+    pub fn from_path(path: &Path) -> Option<ArchiveKind> {
+
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+        match extension.as_str() {
+            | "zip" => Some(ArchiveKind::Zip),
+            | "7z"  => Some(ArchiveKind::SevenZip),
+            | "rar" => Some(ArchiveKind::Rar),
+            | _ => None,
+        }
+    }
+
+    /// The commands listing the entries of `archive`, one per line, in order of preference.
+    fn list_commands(&self, archive: &Path) -> Vec<Vec<String>> {
+
+        let archive = archive.to_string_lossy().into_owned();
+        let seven_zip = |program: &str| vec![program.to_owned(), String::from("l"), String::from("-ba"), archive.clone()];
+
+        match self {
+            | ArchiveKind::Zip => vec![vec![String::from("unzip"), String::from("-Z1"), archive.clone()], seven_zip("7z")],
+            | ArchiveKind::SevenZip => vec![seven_zip("7z"), seven_zip("7za"), seven_zip("7zz")],
+            | ArchiveKind::Rar => vec![vec![String::from("unrar"), String::from("lb"), archive.clone()], seven_zip("7z")],
+        }
+    }
+
+    /// The commands extracting `archive` into `dest`, printing a line per entry, in order of preference.
+    fn extract_commands(&self, archive: &Path, dest: &Path) -> Vec<Vec<String>> {
+
+        let archive = archive.to_string_lossy().into_owned();
+        let dest = dest.to_string_lossy().into_owned();
+        let seven_zip = |program: &str| {
+            vec![program.to_owned(), String::from("x"), String::from("-y"), String::from("-bb1"), format!("-o{}", dest), archive.clone()]
+        };
+
+        match self {
+            // Japanese releases name their entries in Shift-JIS, without the UTF-8 flag.
+            | ArchiveKind::Zip => vec![
+                vec![String::from("unzip"), String::from("-O"), String::from("CP932"), String::from("-o"), archive.clone(), String::from("-d"), dest.clone()],
+                vec![String::from("unzip"), String::from("-o"), archive.clone(), String::from("-d"), dest.clone()],
+                vec![String::from("7z"), String::from("x"), String::from("-y"), String::from("-bb1"), String::from("-mcp=932"), format!("-o{}", dest), archive.clone()],
+            ],
+            | ArchiveKind::SevenZip => vec![seven_zip("7z"), seven_zip("7za"), seven_zip("7zz")],
+            | ArchiveKind::Rar => vec![
+                vec![String::from("unrar"), String::from("x"), String::from("-o+"), String::from("-y"), archive.clone(), format!("{}/", dest)],
+                seven_zip("7z"),
+            ],
+        }
+    }
+}
+
+/// A step of an `Installation`.
+pub enum InstallEvent {
+    /// `done` of the `total` entries of the archive are extracted. `total` is 0 when it is unknown.
+    Progress { done: usize, total: usize },
+    Finished(Result<Installed, String>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Installed {
+
+    pub dir: PathBuf,
+    pub exe: PathBuf,
+    pub title: Option<&'static TouhouTitle>,
+}
+
+/// An archive being extracted into the library in the background.
+pub struct Installation {
+
+    pub archive_name: String,
+    receiver: Receiver<InstallEvent>,
+}
+
+impl Installation {
+
+    /// Start extracting `archive` into a new directory of `library_dir`, named after the archive.
+    pub fn start(archive: &Path, library_dir: &Path) -> Result<Installation, String> {
+
+        let kind = ArchiveKind::from_path(archive)
+            .ok_or_else(|| format!("{} is not a zip, 7z or rar archive.", archive.display()))?;
+        if !archive.is_file() {
+            return Err(format!("{} does not exist.", archive.display()))
+        }
+
+        let stem = archive.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        let dest = (1..).map(|index| match index {
+                | 1 => library_dir.join(&stem),
+                | index => library_dir.join(format!("{} ({})", stem, index)),
+            })
+            .find(|dest| !dest.exists())
+            .unwrap_or_default();
+        fs::create_dir_all(&dest)
+            .map_err(|e| format!("Failed to create {}: {}", dest.display(), e))?;
+
+        let (sender, receiver) = mpsc::channel();
+        let archive = archive.to_path_buf();
+        thread::spawn(move || {
+            let result = install(kind, &archive, &dest, &sender);
+            if result.is_err() {
+                // only what this installation extracted is removed.
+                let _ = fs::remove_dir_all(&dest);
+            }
+            let _ = sender.send(InstallEvent::Finished(result));
+        });
+
+        Ok(Installation { archive_name: stem, receiver })
+    }
+
+    /// The events since the last call.
+    pub fn poll(&self) -> Vec<InstallEvent> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+fn install(kind: ArchiveKind, archive: &Path, dest: &Path, sender: &Sender<InstallEvent>) -> Result<Installed, String> {
+
+    let total = kind.list_commands(archive).iter()
+        .find_map(|words| run(words).ok().filter(|output| output.status.success()))
+        .map_or(0, |output| {
+            String::from_utf8_lossy(&output.stdout).lines().filter(|line| !line.ends_with('/')).count()
+        });
+    let _ = sender.send(InstallEvent::Progress { done: 0, total });
+
+    let mut last_error = String::from("no extraction program was found, install unzip, 7z or unrar");
+    let mut is_extracted = false;
+
+    for words in kind.extract_commands(archive, dest).iter() {
+        let child = match spawn(words) {
+            | Ok(child) => child,
+            | Err(ref e) if e.kind() == io::ErrorKind::NotFound => continue,
+            | Err(e) => return Err(format!("Failed to run `{}`: {}", words[0], e)),
+        };
+
+        let (status, stderr) = follow(child, total, sender)
+            .map_err(|e| format!("Failed to run `{}`: {}", words[0], e))?;
+        if status.success() {
+            is_extracted = true;
+            break
+        }
+
+        let stderr = String::from_utf8_lossy(&stderr);
+        last_error = format!("`{}` exited with {}: {}", words[0], status, stderr.lines().last().unwrap_or(""));
+    }
+
+    if !is_extracted {
+        return Err(last_error)
+    }
+
+    if kind == ArchiveKind::Zip {
+        decode_names(dest);
+    }
+
+    let exe = main_executable(dest)
+        .ok_or_else(|| format!("{} contains no Windows executable.", archive.display()))?;
+    let title = titles::by_executable(&exe.file_name().unwrap_or_default().to_string_lossy());
+
+    Ok(Installed { dir: dest.to_path_buf(), exe, title })
+}
+
+/// Wait for the extraction of `child`, reporting its progress from the entries it prints,
+/// and return its exit status and its error output.
+fn follow(mut child: Child, total: usize, sender: &Sender<InstallEvent>) -> io::Result<(ExitStatus, Vec<u8>)> {
+
+    // read aside, the program would block on a full stderr pipe while stdout is read.
+    let stderr = child.stderr.take().map(|mut stderr| thread::spawn(move || {
+        let mut bytes = vec![];
+        let _ = stderr.read_to_end(&mut bytes);
+        bytes
+    }));
+
+    let mut done = 0;
+    if let Some(stdout) = child.stdout.take() {
+        // the names of the entries may not be UTF-8.
+        for line in BufReader::new(stdout).split(b'\n').map_while(Result::ok) {
+            if is_entry_line(&String::from_utf8_lossy(&line)) {
+                done += 1;
+                // the listing and the extraction may not count the directories alike.
+                let total = if total == 0 { 0 } else { total.max(done) };
+                let _ = sender.send(InstallEvent::Progress { done, total });
+            }
+        }
+    }
+
+    let status = child.wait()?;
+    let stderr = stderr.and_then(|handle| handle.join().ok()).unwrap_or_default();
+    Ok((status, stderr))
+}
+
+/// Rename the entries of `dir` still named in Shift-JIS, as extracted by an `unzip` without `-O`.
+fn decode_names(dir: &Path) {
+
+    let entries = match fs::read_dir(dir) {
+        | Ok(entries) => entries,
+        | Err(_) => return,
+    };
+
+    for entry in entries.filter_map(Result::ok) {
+        let mut path = entry.path();
+
+        if entry.file_name().to_str().is_none() {
+            if let Some(name) = SHIFT_JIS.decode_without_bom_handling_and_without_replacement(entry.file_name().as_bytes()) {
+                let decoded = dir.join(&*name);
+                if !decoded.exists() && fs::rename(&path, &decoded).is_ok() {
+                    path = decoded;
+                }
+            }
+        }
+
+        if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+            decode_names(&path);
+        }
+    }
+}
+
+fn run(words: &[String]) -> io::Result<Output> {
+    Command::new(&words[0]).args(&words[1..]).stdin(Stdio::null()).output()
+}
+
+fn spawn(words: &[String]) -> io::Result<Child> {
+
+    Command::new(&words[0])
+        .args(&words[1..])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+}
+
+/// Whether an output line of the extraction programs reports an extracted file.
+fn is_entry_line(line: &str) -> bool {
+
+    let line = line.trim_start();
+    // unzip, 7z with `-bb1` and unrar, which also prints `Extracting from <archive>` first.
+    line.starts_with("inflating:") || line.starts_with("extracting:")
+        || line.starts_with("- ") || (line.starts_with("Extracting ") && !line.starts_with("Extracting from "))
+}
+
+/// The executable of the game in `dir`: one known by the title database, or else the one
+/// nearest to `dir` that is not a tool, the largest first.
+pub fn main_executable(dir: &Path) -> Option<PathBuf> {
+
+    let mut executables = vec![];
+    collect_executables(dir, 0, &mut executables);
+
+    executables.into_iter()
+        .min_by_key(|(path, depth)| {
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
+            let is_known = titles::by_executable(&file_name).is_some();
+            let is_auxiliary = AUXILIARY_WORDS.iter().any(|word| file_name.contains(word));
+            let size = fs::metadata(path).map_or(0, |metadata| metadata.len());
+            (!is_known, is_auxiliary, *depth, std::cmp::Reverse(size))
+        })
+        .map(|(path, _)| path)
+}
+
+fn collect_executables(dir: &Path, depth: usize, executables: &mut Vec<(PathBuf, usize)>) {
+
+    let entries = match fs::read_dir(dir) {
+        | Ok(entries) => entries,
+        | Err(_) => return,
+    };
+
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        match entry.file_type() {
+            | Ok(file_type) if file_type.is_dir() && depth < MAX_EXE_DEPTH => collect_executables(&path, depth + 1, executables),
+            | Ok(file_type) if file_type.is_file() => {
+                if path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("exe")) {
+                    executables.push((path, depth));
+                }
+            },
+            | _ => {},
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::utils::testing::scratch_dir;
+
+    use std::ffi::OsStr;
+
+    #[test]
+    fn counts_the_extracted_entries() {
+
+        let lines = [
+            ("  inflating: th08/th08.exe", true),
+            (" extracting: th08/thbgm.dat", true),
+            ("   creating: th08/replay/", false),
+            ("- th08/th08.exe", true),
+            ("Extracting from /home/reimu/th08.rar", false),
+            ("Extracting  th08/th08.exe                                        OK ", true),
+            ("Creating    th08/replay                                          OK", false),
+            ("Archive:  th08.zip", false),
+            ("All OK", false),
+        ];
+        for (line, is_entry) in lines.iter() {
+            assert_eq!(is_entry_line(line), *is_entry, "{}", line);
+        }
+    }
+
+    #[test]
+    fn reads_the_errors_of_a_verbose_extraction() {
+
+        // more than a pipe holds on stderr, before and while stdout is written.
+        let script = "head -c 200000 /dev/zero >&2; for i in 1 2 3; do echo \"- file$i\"; done; echo broken >&2; exit 2";
+        let child = spawn(&[String::from("sh"), String::from("-c"), String::from(script)]).unwrap();
+        let (sender, receiver) = mpsc::channel();
+
+        let (status, stderr) = follow(child, 2, &sender).unwrap();
+
+        assert_eq!(status.code(), Some(2));
+        assert_eq!(stderr.len(), 200000 + "broken\n".len());
+        let progress: Vec<(usize, usize)> = receiver.try_iter()
+            .filter_map(|event| match event {
+                | InstallEvent::Progress { done, total } => Some((done, total)),
+                | InstallEvent::Finished(_) => None,
+            })
+            .collect();
+        assert_eq!(progress, [(1, 2), (2, 2), (3, 3)]);
+    }
+
+    #[test]
+    fn decodes_the_names_extracted_in_shift_jis() {
+
+        let dir = scratch_dir("install-decode");
+        // 東方 and 紅魔郷.exe in Shift-JIS.
+        let folder = dir.join(OsStr::from_bytes(b"\x93\x8c\x95\xfb"));
+        fs::create_dir_all(&folder).unwrap();
+        fs::write(folder.join(OsStr::from_bytes(b"\x8d\x67\x96\x82\x8b\xbd.exe")), b"MZ").unwrap();
+        fs::write(dir.join("readme.txt"), b"").unwrap();
+
+        decode_names(&dir);
+
+        assert!(dir.join("東方/紅魔郷.exe").is_file());
+        assert!(dir.join("readme.txt").is_file());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,211 @@
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{ Path, PathBuf };
+
+use crate::config::tab::ItemConfig;
+use crate::launch::command::split_words;
+use crate::library::scan::Discovery;
+use crate::library::titles;
+
+use serde_yaml::Value;
+
+/// The directories of the game configs of Lutris, below the home directory, newest first.
+const DEFAULT_LUTRIS_DIRS: &[&str] = &[".local/share/lutris/games", ".config/lutris/games"];
+
+/// The parts of a Lutris game config read by the import, such as:
+///
+/// ```yaml
+/// game:
+///   exe: /home/reimu/Games/th08/th08.exe
+///   prefix: /home/reimu/Games/touhou
+///   args: -w
+/// system:
+///   env:
+///     LANG: ja_JP.UTF-8
+/// wine:
+///   version: lutris-7.2-x86_64
+/// ```
+#[derive(Debug, Deserialize)]
+struct LutrisConfig {
+
+    game: Option<GameSection>,
+    system: Option<SystemSection>,
+    wine: Option<WineSection>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GameSection {
+
+    exe: Option<String>,
+    prefix: Option<String>,
+    args: Option<String>,
+    working_dir: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SystemSection {
+
+    /// Values such as `DXVK_HUD: 1` are not always quoted.
+    #[serde(default)]
+    env: BTreeMap<String, Value>,
+    locale: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WineSection {
+
+    version: Option<String>,
+}
+
+/// The Lutris games directory to import from, the configured one or the first existing default.
+pub fn find_dir(configured: Option<&str>) -> Option<PathBuf> {
+
+    if let Some(dir) = configured {
+        return Some(PathBuf::from(dir))
+    }
+
+    let home = PathBuf::from(env::var_os("HOME")?);
+    DEFAULT_LUTRIS_DIRS.iter()
+        .map(|dir| home.join(dir))
+        .find(|dir| dir.is_dir())
+}
+
+/// The Touhou games configured in the `*.yml` files of `dir`, and the problems of the files that were skipped.
+pub fn import(dir: &Path) -> Result<(Vec<Discovery>, Vec<String>), String> {
+
+    let entries = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+
+    let mut paths: Vec<PathBuf> = entries.filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "yml" || extension == "yaml"))
+        .collect();
+    paths.sort();
+
+    let mut found = vec![];
+    let mut warnings = vec![];
+    for path in paths.iter() {
+        let config = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+            .and_then(|text| serde_yaml::from_str(&text).map_err(|e| format!("Failed to parse {}: {}", path.display(), e)));
+        match config {
+            | Ok(config) => found.extend(discover(&config, dir)),
+            | Err(e) => warnings.push(e),
+        }
+    }
+
+    Ok((found, warnings))
+}
+
+/// The game of a Lutris config, if it is a Touhou game.
+fn discover(config: &LutrisConfig, dir: &Path) -> Option<Discovery> {
+
+    let game = config.game.as_ref()?;
+    let text = |value: &Option<String>| value.clone().filter(|value| !value.is_empty());
+
+    let path = text(&game.exe)?;
+    let title = titles::by_executable(path.rsplit(['/', '\\']).next()?)?;
+
+    let system = config.system.as_ref();
+    let mut env: BTreeMap<String, String> = system.map(|system| &system.env).into_iter()
+        .flatten()
+        .filter_map(|(key, value)| {
+            let value = match value {
+                | Value::String(value) => value.clone(),
+                | Value::Number(value) => value.to_string(),
+                | Value::Bool(value) => value.to_string(),
+                | _ => return None,
+            };
+            Some((key.clone(), value))
+        })
+        .collect();
+    if let Some(prefix) = text(&game.prefix) {
+        env.entry(String::from("WINEPREFIX")).or_insert(prefix);
+    }
+
+    let runner = match config.wine {
+        | Some(ref wine) => Some(wine_runner(text(&wine.version).as_deref(), dir)),
+        | None if path.to_ascii_lowercase().ends_with(".exe") => Some(String::from("wine")),
+        | None => None,
+    };
+
+    let item = ItemConfig {
+        name: title.display_name(),
+        args: text(&game.args).map(|args| split_words(&args)).unwrap_or_default(),
+        cwd: text(&game.working_dir),
+        locale: system.and_then(|system| text(&system.locale)),
+        path,
+        runner,
+        env,
+        ..ItemConfig::default()
+    };
+
+    Some(Discovery { group: title.group, title, item })
+}
+
+/// The Wine of the Lutris runner `version`, installed under `runners/wine` next to the games directory
+/// or in `~/.local/share/lutris`. Falls back to the system `wine` when it is not installed.
+fn wine_runner(version: Option<&str>, dir: &Path) -> String {
+
+    let version = match version.filter(|version| *version != "system") {
+        | Some(version) => version,
+        | None => return String::from("wine"),
+    };
+
+    let data_dirs = dir.parent().map(Path::to_path_buf).into_iter()
+        .chain(env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share/lutris")));
+
+    data_dirs.map(|data_dir| data_dir.join("runners").join("wine").join(version).join("bin").join("wine"))
+        .find(|wine| wine.is_file())
+        .map_or_else(|| String::from("wine"), |wine| wine.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::utils::testing::fixture;
+
+    #[test]
+    fn imports_the_touhou_games_and_skips_broken_configs() {
+
+        let dir = fixture("lutris/games");
+        let (found, warnings) = import(&dir).unwrap();
+
+        let names: Vec<&str> = found.iter().map(|discovery| discovery.title.id).collect();
+        assert_eq!(names, ["th08", "th07"]);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with(&format!("Failed to parse {}: ", dir.join("broken-1700000002.yml").display())));
+
+        let th08 = &found[0].item;
+        assert_eq!(th08.path, "/home/reimu/Games/th08/th08.exe");
+        assert_eq!(th08.args, ["-w"]);
+        assert_eq!(th08.cwd.as_deref(), Some("/home/reimu/Games/th08"));
+        assert_eq!(th08.locale.as_deref(), Some("ja_JP.UTF-8"));
+        assert_eq!(th08.env.get("WINEPREFIX").map(String::as_str), Some("/home/reimu/Games/touhou"));
+        assert_eq!(th08.env.get("LANG").map(String::as_str), Some("ja_JP.UTF-8"));
+        assert_eq!(th08.runner, Some(fixture("lutris/runners/wine/lutris-7.2-x86_64/bin/wine").to_string_lossy().into_owned()));
+
+        let th07 = &found[1].item;
+        assert_eq!(th07.runner.as_deref(), Some("wine"));
+        assert_eq!(th07.cwd, None);
+    }
+
+    #[test]
+    fn reads_unquoted_environment_values() {
+
+        let config: LutrisConfig = serde_yaml::from_str("game:\n  exe: C:\\Games\\th06.exe\nsystem:\n  env:\n    DXVK_HUD: 1\n    PULSE_LATENCY_MSEC: '60'\n    UNSET: null\n").unwrap();
+        let item = discover(&config, &fixture("lutris/games")).unwrap().item;
+
+        let env: Vec<(&str, &str)> = item.env.iter().map(|(key, value)| (key.as_str(), value.as_str())).collect();
+        assert_eq!(env, [("DXVK_HUD", "1"), ("PULSE_LATENCY_MSEC", "60")]);
+        assert_eq!(item.runner.as_deref(), Some("wine"));
+    }
+
+    #[test]
+    fn reports_a_missing_directory() {
+        assert!(import(&fixture("lutris/missing")).is_err());
+    }
+}
@@ -0,0 +1,33 @@
+
+pub mod scan;
+pub mod titles;
+pub mod sha256;
+pub mod versions;
+pub mod health;
+pub mod vdf;
+pub mod steam;
+pub mod shortcuts;
+pub mod desktop;
+pub mod lutris;
+pub mod wineprefix;
+pub mod install;
+
+/// The tab a discovered game is filed under.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameGroup {
+    Mainline,
+    Fighting,
+    SpinOff,
+}
+
+impl GameGroup {
+
+    pub fn tab_name(&self) -> &'static str {
+
+        match self {
+            | GameGroup::Mainline => "Mainline",
+            | GameGroup::Fighting => "Fighting",
+            | GameGroup::SpinOff  => "Spin-off",
+        }
+    }
+}
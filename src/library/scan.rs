@@ -0,0 +1,246 @@
+
+use std::collections::{ BTreeMap, HashSet };
+use std::fs;
+use std::path::{ Path, PathBuf };
+use std::sync::mpsc::{ self, Receiver };
+use std::thread;
+
+use crate::config::ConfigOp;
+use crate::config::tab::{ TabConfig, ItemConfig };
+use crate::library::GameGroup;
+use crate::library::steam;
+use crate::library::titles::{ self, TouhouTitle };
+
+/// How deep below a root directory executables are looked for.
+const MAX_SCAN_DEPTH: usize = 5;
+
+/// A game found by `scan`, proposed to be added to the library.
+#[derive(Debug, Clone)]
+pub struct Discovery {
+
+    pub group: GameGroup,
+    pub title: &'static TouhouTitle,
+    pub item: ItemConfig,
+}
+
+/// A scan of the roots and of the Steam libraries, run on a background thread since walking a large
+/// or network drive keeps the interface from responding.
+pub struct LibraryScan {
+
+    receiver: Receiver<(Vec<Discovery>, Option<String>)>,
+}
+
+impl LibraryScan {
+
+    /// Scan `roots` and, when `steam_root` is set, its libraries for the apps not in `imported`.
+    pub fn start(roots: Vec<PathBuf>, steam_root: Option<PathBuf>, imported: HashSet<String>, app_ids: BTreeMap<String, String>, known: HashSet<PathBuf>) -> LibraryScan {
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut found = scan(&roots);
+            let mut steam_error = None;
+            if let Some(ref steam_root) = steam_root {
+                match steam::scan(steam_root, &imported, &app_ids) {
+                    | Ok(discoveries) => found.extend(discoveries),
+                    | Err(e) => steam_error = Some(e),
+                }
+            }
+            let _ = sender.send((arrange(found, &known), steam_error));
+        });
+
+        LibraryScan { receiver }
+    }
+
+    /// The new games found, and the error of the Steam scan if any, once the scan is done.
+    pub fn poll(&self) -> Option<(Vec<Discovery>, Option<String>)> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+/// Walk `roots` for the executables of the games.
+pub fn scan(roots: &[PathBuf]) -> Vec<Discovery> {
+
+    let mut found = vec![];
+    for root in roots.iter() {
+        walk(root, 0, &mut found);
+    }
+    found
+}
+
+/// Leave out the paths in `known` and the duplicates of `found`, grouped by tab in release order.
+pub fn arrange(mut found: Vec<Discovery>, known: &HashSet<PathBuf>) -> Vec<Discovery> {
+
+    found.retain(|discovery: &Discovery| !known.contains(Path::new(&discovery.item.path)));
+    // the copies with actions, such as the ones found in a Steam library, are kept first.
+    found.sort_by(|a, b| {
+        (a.group.tab_name(), a.title.sort_key(), &a.item.path, &a.item.args, a.item.actions.is_empty())
+            .cmp(&(b.group.tab_name(), b.title.sort_key(), &b.item.path, &b.item.args, b.item.actions.is_empty()))
+    });
+    found.dedup_by(|a, b| a.item.path == b.item.path && a.item.args == b.item.args);
+    found
+}
+
+fn walk(dir: &Path, depth: usize, found: &mut Vec<Discovery>) {
+
+    let entries = match fs::read_dir(dir) {
+        | Ok(entries) => entries,
+        | Err(_) => return,
+    };
+
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        // symbolic links are skipped, Wine prefixes link to the whole file system.
+        let file_type = match entry.file_type() {
+            | Ok(file_type) => file_type,
+            | Err(_) => continue,
+        };
+
+        if file_type.is_dir() {
+            if depth < MAX_SCAN_DEPTH {
+                walk(&path, depth + 1, found);
+            }
+        } else if file_type.is_file() {
+            if let Some(title) = titles::by_executable(&entry.file_name().to_string_lossy()) {
+                let item = ItemConfig {
+                    name: title.display_name(),
+                    path: path.to_string_lossy().into_owned(),
+                    ..ItemConfig::default()
+                };
+                found.push(Discovery { group: title.group, title, item });
+            }
+        }
+    }
+}
+
+/// Turn the accepted discoveries into the operations adding them, creating the missing tabs.
+pub fn into_ops(discoveries: Vec<Discovery>, tabs: &[TabConfig]) -> ConfigOp {
+
+    let mut ops = vec![];
+    let mut new_tabs: Vec<TabConfig> = vec![];
+
+    for Discovery { group, item, .. } in discoveries.into_iter() {
+        let tab_name = group.tab_name();
+
+        if let Some(tab_index) = tabs.iter().position(|tab| tab.name.eq_ignore_ascii_case(tab_name)) {
+            ops.push(ConfigOp::AppendGame { tab_index, config: Box::new(item) });
+        } else if let Some(tab) = new_tabs.iter_mut().find(|tab| tab.name == tab_name) {
+            tab.items.push(item);
+        } else {
+            new_tabs.push(TabConfig { name: String::from(tab_name), items: vec![item], ..TabConfig::default() });
+        }
+    }
+
+    ops.extend(new_tabs.into_iter().map(|config| ConfigOp::AppendTab { config }));
+    ConfigOp::Batch(ops)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::time::Duration;
+
+    use crate::config::tab::ActionConfig;
+    use crate::utils::testing::{ fixture, scratch_dir };
+
+    fn discovery(id: &str, path: &str) -> Discovery {
+
+        let title = titles::by_id(id).unwrap();
+        let item = ItemConfig { name: title.display_name(), path: String::from(path), ..ItemConfig::default() };
+        Discovery { group: title.group, title, item }
+    }
+
+    #[test]
+    fn finds_the_executables_below_the_roots() {
+
+        let dir = scratch_dir("scan-roots");
+        for path in ["th08/th08.exe", "Touhou/th123/TH123.EXE", "th08/custom.exe", "th08/readme.txt"].iter() {
+            fs::create_dir_all(dir.join(path).parent().unwrap()).unwrap();
+            fs::write(dir.join(path), "").unwrap();
+        }
+
+        let mut found: Vec<_> = scan(&[dir.clone(), dir.join("missing")]).into_iter()
+            .map(|discovery| (discovery.title.id, discovery.group, discovery.item.name))
+            .collect();
+        found.sort_by_key(|(id, _, _)| *id);
+        assert_eq!(found, vec![
+            ("th08", GameGroup::Mainline, String::from("th08 Imperishable Night")),
+            ("th123", GameGroup::Fighting, String::from("th123 Hisoutensoku")),
+        ]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn arranges_the_new_games_by_tab_in_release_order() {
+
+        let found = vec![
+            discovery("th10", "/games/th10/th10.exe"),
+            discovery("th123", "/games/th123/th123.exe"),
+            discovery("th08", "/games/th08/th08.exe"),
+            discovery("th095", "/games/th095/th095.exe"),
+            discovery("th06", "/games/th06/th06.exe"),
+            discovery("th075", "/games/th075/th075.exe"),
+        ];
+        let known: HashSet<_> = vec![PathBuf::from("/games/th10/th10.exe")].into_iter().collect();
+
+        let paths: Vec<_> = arrange(found, &known).into_iter().map(|discovery| discovery.item.path).collect();
+        assert_eq!(paths, vec![
+            "/games/th075/th075.exe",
+            "/games/th123/th123.exe",
+            "/games/th06/th06.exe",
+            "/games/th08/th08.exe",
+            "/games/th095/th095.exe",
+        ]);
+    }
+
+    #[test]
+    fn keeps_one_copy_of_each_game_preferring_the_one_with_actions() {
+
+        let mut steam = discovery("th08", "/games/th08/th08.exe");
+        steam.item.actions.push(ActionConfig { name: String::from("Manual"), ..ActionConfig::default() });
+        let mut patched = discovery("th08", "/games/th08/th08.exe");
+        patched.item.args.push(String::from("--lang=en"));
+
+        let found = vec![discovery("th08", "/games/th08/th08.exe"), patched, steam];
+        let arranged = arrange(found, &HashSet::new());
+
+        assert_eq!(arranged.len(), 2);
+        assert_eq!(arranged[0].item.args, Vec::<String>::new());
+        assert_eq!(arranged[0].item.actions.len(), 1);
+        assert_eq!(arranged[1].item.args, vec!["--lang=en"]);
+    }
+
+    #[test]
+    fn scans_the_roots_and_steam_in_the_background() {
+
+        let dir = scratch_dir("scan-background");
+        fs::create_dir_all(dir.join("th06")).unwrap();
+        fs::write(dir.join("th06/th06.exe"), "").unwrap();
+
+        let known: HashSet<_> = vec![fixture("steam/steamapps/common/th17/th17.exe")].into_iter().collect();
+        let scan = LibraryScan::start(vec![dir.clone()], Some(fixture("steam")), HashSet::new(), BTreeMap::new(), known);
+        let (found, steam_error) = loop {
+            match scan.poll() {
+                | Some(result) => break result,
+                | None => thread::sleep(Duration::from_millis(10)),
+            }
+        };
+
+        let ids: Vec<_> = found.iter().map(|discovery| discovery.title.id).collect();
+        assert_eq!(ids, vec!["th06", "th16", "th18"]);
+        assert!(steam_error.is_none());
+
+        let scan = LibraryScan::start(vec![], Some(dir.clone()), HashSet::new(), BTreeMap::new(), HashSet::new());
+        let steam_error = loop {
+            match scan.poll() {
+                | Some((_, steam_error)) => break steam_error,
+                | None => thread::sleep(Duration::from_millis(10)),
+            }
+        };
+        assert!(steam_error.unwrap().contains("libraryfolders.vdf"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,57 @@
+
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use sha2::{ Digest, Sha256 };
+
+/// The SHA-256 of `data` in lowercase hexadecimal.
+pub fn hex_digest(data: &[u8]) -> String {
+    to_hex(&Sha256::digest(data))
+}
+
+/// Hash the content of the file at `path`.
+pub fn hash_file(path: &Path) -> io::Result<String> {
+
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+
+    Ok(to_hex(&hasher.finalize()))
+}
+
+fn to_hex(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::fs;
+
+    use crate::utils::testing::scratch_dir;
+
+    #[test]
+    fn hashes_the_fips_180_2_examples() {
+
+        assert_eq!(hex_digest(b""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert_eq!(hex_digest(b"abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(hex_digest(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1");
+    }
+
+    #[test]
+    fn hashes_files_larger_than_a_block() {
+
+        let dir = scratch_dir("sha256-file");
+        let path = dir.join("th08.exe");
+        fs::write(&path, vec![b'a'; 1_000_000]).unwrap();
+
+        assert_eq!(hash_file(&path).unwrap(), "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0");
+        assert!(hash_file(&dir.join("missing.exe")).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,257 @@
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{ Path, PathBuf };
+
+use crate::config::tab::{ TabConfig, ItemConfig };
+use crate::library::desktop;
+use crate::library::vdf::BinaryVdf;
+
+/// A game added to Steam as a non-Steam shortcut.
+#[derive(Debug, Clone)]
+pub struct Shortcut {
+
+    pub name: String,
+    pub exe: String,
+    pub start_dir: String,
+    pub launch_options: String,
+    /// The collections of the shortcut in Steam, such as the tab of the game.
+    pub tags: Vec<String>,
+}
+
+impl Shortcut {
+
+    /// The shortcut running `item` through `th-launcher run`, like the desktop entries, so that hooks,
+    /// prefixes and the watchdog apply. It starts in `manifest_dir`, where the launcher finds its manifest.
+    pub fn for_item(tab: &TabConfig, item: &ItemConfig, launcher: &Path, manifest_dir: &Path) -> Shortcut {
+
+        Shortcut {
+            name: item.name.clone(),
+            exe: launcher.to_string_lossy().into_owned(),
+            start_dir: manifest_dir.to_string_lossy().into_owned(),
+            launch_options: format!("run {}", quote(&desktop::entry_id(tab, item))),
+            tags: vec![tab.name.clone()],
+        }
+    }
+
+    /// The id Steam derives for the shortcut, used by its artwork and controller files.
+    pub fn app_id(&self) -> u32 {
+        crc32fast::hash(format!("{}{}", quote_always(&self.exe), self.name).as_bytes()) | 0x8000_0000
+    }
+
+    fn to_vdf(&self) -> BinaryVdf {
+
+        let string = |value: &str| BinaryVdf::String(value.to_owned());
+        let tags = self.tags.iter().enumerate()
+            .map(|(index, tag)| (index.to_string(), string(tag)))
+            .collect();
+
+        BinaryVdf::Map(vec![
+            (String::from("appid"), BinaryVdf::Int32(self.app_id() as i32)),
+            (String::from("AppName"), string(&self.name)),
+            (String::from("Exe"), string(&quote_always(&self.exe))),
+            (String::from("StartDir"), string(&quote_always(&self.start_dir))),
+            (String::from("icon"), string("")),
+            (String::from("ShortcutPath"), string("")),
+            (String::from("LaunchOptions"), string(&self.launch_options)),
+            (String::from("IsHidden"), BinaryVdf::Int32(0)),
+            (String::from("AllowDesktopConfig"), BinaryVdf::Int32(1)),
+            (String::from("AllowOverlay"), BinaryVdf::Int32(1)),
+            (String::from("OpenVR"), BinaryVdf::Int32(0)),
+            (String::from("Devkit"), BinaryVdf::Int32(0)),
+            (String::from("DevkitGameID"), string("")),
+            (String::from("DevkitOverrideAppID"), BinaryVdf::Int32(0)),
+            (String::from("LastPlayTime"), BinaryVdf::Int32(0)),
+            (String::from("FlatpakAppID"), string("")),
+            (String::from("tags"), BinaryVdf::Map(tags)),
+        ])
+    }
+}
+
+/// The `shortcuts.vdf` of every Steam account that logged in at `steam_root`.
+pub fn shortcut_files(steam_root: &Path) -> Vec<PathBuf> {
+
+    let entries = match fs::read_dir(steam_root.join("userdata")) {
+        | Ok(entries) => entries,
+        | Err(_) => return vec![],
+    };
+
+    let mut files: Vec<PathBuf> = entries.filter_map(Result::ok)
+        .map(|entry| entry.path().join("config"))
+        .filter(|config| config.is_dir())
+        .map(|config| config.join("shortcuts.vdf"))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Add `shortcuts` to the `shortcuts.vdf` at `path`, keeping its entries and skipping the shortcuts
+/// with the name, executable and launch options of an existing one. Returns how many were added.
+///
+/// The previous file is kept as `shortcuts.vdf.bak`. Steam rewrites the file when it exits,
+/// so it has to be closed for the shortcuts to stay.
+pub fn export(path: &Path, shortcuts: &[Shortcut]) -> Result<usize, String> {
+
+    let mut root = if path.exists() {
+        let bytes = fs::read(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        BinaryVdf::decode(&bytes)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?
+    } else {
+        BinaryVdf::Map(vec![])
+    };
+
+    let entries = match root {
+        | BinaryVdf::Map(ref mut root_entries) => {
+            if !root_entries.iter().any(|(key, _)| key.eq_ignore_ascii_case("shortcuts")) {
+                root_entries.push((String::from("shortcuts"), BinaryVdf::Map(vec![])));
+            }
+            match root_entries.iter_mut().find(|(key, _)| key.eq_ignore_ascii_case("shortcuts")) {
+                | Some((_, BinaryVdf::Map(entries))) => entries,
+                | _ => return Err(format!("{} has no list of shortcuts.", path.display())),
+            }
+        },
+        | _ => unreachable!("decoded files are maps"),
+    };
+
+    let mut existing: HashSet<(String, String, String)> = entries.iter()
+        .filter_map(|(_, entry)| {
+            let name = entry.get("AppName")?.as_str()?;
+            let exe = entry.get("Exe")?.as_str()?;
+            let options = entry.get("LaunchOptions").and_then(BinaryVdf::as_str).unwrap_or_default();
+            Some(identity(name, exe, options))
+        })
+        .collect();
+    let mut next_index = entries.iter()
+        .filter_map(|(key, _)| key.parse::<usize>().ok())
+        .max()
+        .map_or(0, |index| index + 1);
+
+    let mut added = 0;
+    for shortcut in shortcuts.iter() {
+        if existing.insert(identity(&shortcut.name, &shortcut.exe, &shortcut.launch_options)) {
+            entries.push((next_index.to_string(), shortcut.to_vdf()));
+            next_index += 1;
+            added += 1;
+        }
+    }
+
+    if added > 0 {
+        if path.exists() {
+            fs::copy(path, path.with_extension("vdf.bak"))
+                .map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+        }
+        fs::write(path, root.encode())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+
+    Ok(added)
+}
+
+/// What makes two shortcuts the same, Steam may or may not quote the executable.
+/// Every game runs the launcher, so the launch options tell them apart.
+fn identity(name: &str, exe: &str, options: &str) -> (String, String, String) {
+    (name.to_owned(), exe.trim_matches('"').to_owned(), options.to_owned())
+}
+
+fn quote_always(value: &str) -> String {
+    format!("\"{}\"", value)
+}
+
+/// Quote `value` for the launch options when it contains spaces.
+fn quote(value: &str) -> String {
+
+    if value.is_empty() || value.contains(char::is_whitespace) {
+        quote_always(value)
+    } else {
+        value.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::utils::testing::{ fixture, fixture_copy };
+
+    fn shortcut(tab: &str, name: &str) -> Shortcut {
+
+        let tab = TabConfig { name: tab.to_owned(), ..TabConfig::default() };
+        let item = ItemConfig { name: name.to_owned(), ..ItemConfig::default() };
+        Shortcut::for_item(&tab, &item, Path::new("/usr/bin/th-launcher"), Path::new("/home/user/touhou"))
+    }
+
+    #[test]
+    fn runs_games_through_the_launcher() {
+
+        let shortcut = shortcut("Main line", "th08");
+
+        assert_eq!(shortcut.exe, "/usr/bin/th-launcher");
+        assert_eq!(shortcut.start_dir, "/home/user/touhou");
+        assert_eq!(shortcut.launch_options, "run \"Main line/th08\"");
+        assert_eq!(shortcut.tags, vec![String::from("Main line")]);
+    }
+
+    #[test]
+    fn finds_the_files_of_each_account() {
+
+        let root = fixture("steam");
+        assert_eq!(shortcut_files(&root), vec![root.join("userdata/10000001/config/shortcuts.vdf")]);
+    }
+
+    #[test]
+    fn exports_after_the_existing_shortcuts() {
+
+        let dir = fixture_copy("steam/userdata/10000001/config", "shortcuts-export");
+        let path = dir.join("shortcuts.vdf");
+        let original = fs::read(&path).unwrap();
+
+        // the same name in two tabs is two games.
+        let shortcuts = vec![shortcut("Mainline", "th08"), shortcut("Patched", "th08")];
+        assert_eq!(export(&path, &shortcuts).unwrap(), 2);
+        assert_eq!(export(&path, &shortcuts).unwrap(), 0);
+        assert_eq!(fs::read(path.with_extension("vdf.bak")).unwrap(), original);
+
+        let root = BinaryVdf::decode(&fs::read(&path).unwrap()).unwrap();
+        let entries = match root.get("shortcuts") {
+            | Some(BinaryVdf::Map(entries)) => entries.clone(),
+            | _ => panic!("no shortcuts"),
+        };
+        let keys: Vec<&str> = entries.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, vec!["0", "1", "2", "3"]);
+
+        // the entries already there are kept as they were.
+        let before = BinaryVdf::decode(&original).unwrap();
+        let kept = match before.get("shortcuts") {
+            | Some(BinaryVdf::Map(entries)) => entries.clone(),
+            | _ => panic!("no shortcuts"),
+        };
+        assert_eq!(&entries[..2], &kept[..]);
+
+        let added = &entries[3].1;
+        assert_eq!(added.get("Exe").and_then(BinaryVdf::as_str), Some("\"/usr/bin/th-launcher\""));
+        assert_eq!(added.get("LaunchOptions").and_then(BinaryVdf::as_str), Some("run Patched/th08"));
+        assert_eq!(added.get("appid"), Some(&BinaryVdf::Int32(shortcuts[1].app_id() as i32)));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn computes_the_app_id_of_steam() {
+
+        // the ids of the fixture, written the way Steam derives them.
+        let root = BinaryVdf::decode(&fs::read(fixture("steam/userdata/10000001/config/shortcuts.vdf")).unwrap()).unwrap();
+        let first = root.get("shortcuts").and_then(|shortcuts| shortcuts.get("0")).unwrap();
+
+        let shortcut = Shortcut {
+            name: String::from("RetroArch"),
+            exe: String::from("/usr/bin/retroarch"),
+            start_dir: String::from("/usr/bin/"),
+            launch_options: String::new(),
+            tags: vec![],
+        };
+        assert_eq!(shortcut.app_id(), 0xED86_A348);
+        assert_eq!(first.get("appid"), Some(&BinaryVdf::Int32(shortcut.app_id() as i32)));
+    }
+}
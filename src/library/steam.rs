@@ -0,0 +1,239 @@
+
+use std::collections::{ BTreeMap, HashSet };
+use std::env;
+use std::fs;
+use std::path::{ Path, PathBuf };
+
+use crate::config::tab::{ TabConfig, ItemConfig, ActionConfig };
+use crate::library::scan::{ self, Discovery };
+use crate::library::titles;
+use crate::library::vdf::Vdf;
+
+/// The usual places of the Steam client on Linux, below the home directory.
+const DEFAULT_STEAM_ROOTS: &[&str] = &[".local/share/Steam", ".steam/steam"];
+
+/// A game installed by Steam, read from its `appmanifest_<appid>.acf`.
+#[derive(Debug, Clone)]
+pub struct SteamApp {
+
+    pub app_id: String,
+    pub name: String,
+    /// The directory of the game, `steamapps/common/<installdir>` of its library.
+    pub install_dir: PathBuf,
+}
+
+/// The Steam root to import from, the configured one or the first existing default.
+pub fn find_root(configured: Option<&str>) -> Option<PathBuf> {
+
+    if let Some(root) = configured {
+        return Some(PathBuf::from(root))
+    }
+
+    let home = PathBuf::from(env::var_os("HOME")?);
+    DEFAULT_STEAM_ROOTS.iter()
+        .map(|root| home.join(root))
+        .find(|root| root.join("steamapps").is_dir())
+}
+
+/// The library folders of the Steam installation at `root`, starting with the root itself.
+pub fn library_folders(root: &Path) -> Result<Vec<PathBuf>, String> {
+
+    let path = root.join("steamapps").join("libraryfolders.vdf");
+    let text = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let vdf = Vdf::parse(&text)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+
+    let mut folders = vec![root.to_path_buf()];
+    let entries = vdf.get("libraryfolders").map(Vdf::entries).unwrap_or_default();
+
+    for (key, value) in entries.iter() {
+        // recent clients nest each folder as `"0" { "path" "..." }`, older ones write `"1" "..."`.
+        let folder = match value {
+            | Vdf::Map(_) => value.get("path").and_then(Vdf::as_str),
+            | Vdf::String(path) if key.chars().all(|ch| ch.is_ascii_digit()) => Some(path.as_str()),
+            | Vdf::String(_) => None,
+        };
+
+        if let Some(folder) = folder.map(PathBuf::from) {
+            if !folders.contains(&folder) {
+                folders.push(folder);
+            }
+        }
+    }
+
+    Ok(folders)
+}
+
+/// The games installed in the library folder `folder`, skipping the manifests that can not be read.
+pub fn installed_apps(folder: &Path) -> Vec<SteamApp> {
+
+    let steamapps = folder.join("steamapps");
+    let entries = match fs::read_dir(&steamapps) {
+        | Ok(entries) => entries,
+        | Err(_) => return vec![],
+    };
+
+    let mut apps: Vec<SteamApp> = entries.filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+            file_name.starts_with("appmanifest_") && file_name.ends_with(".acf")
+        })
+        .filter_map(|path| {
+            let vdf = Vdf::parse(&fs::read_to_string(path).ok()?).ok()?;
+            let state = vdf.get("AppState")?;
+            Some(SteamApp {
+                app_id: state.get("appid")?.as_str()?.to_owned(),
+                name: state.get("name").and_then(Vdf::as_str).unwrap_or_default().to_owned(),
+                install_dir: steamapps.join("common").join(state.get("installdir")?.as_str()?),
+            })
+        })
+        .collect();
+
+    apps.sort_by(|a, b| a.app_id.cmp(&b.app_id));
+    apps
+}
+
+/// The command starting `app_id` through the Steam client.
+pub fn launch_action(app_id: &str) -> ActionConfig {
+
+    ActionConfig {
+        name: String::from("Launch via Steam"),
+        path: String::from("steam"),
+        args: vec![String::from("-applaunch"), app_id.to_owned()],
+        cwd: None,
+        runner: Some(String::new()),
+    }
+}
+
+/// The Steam app ids already started by the games of `tabs`, or by their actions.
+pub fn imported_app_ids(tabs: &[TabConfig]) -> HashSet<String> {
+
+    let applaunch_id = |args: &[String]| {
+        args.iter().position(|arg| arg == "-applaunch").and_then(|index| args.get(index + 1)).cloned()
+    };
+
+    tabs.iter()
+        .flat_map(|tab| tab.items.iter())
+        .flat_map(|item| {
+            let actions = item.actions.iter().filter_map(|action| applaunch_id(&action.args));
+            applaunch_id(&item.args).into_iter().chain(actions).collect::<Vec<String>>()
+        })
+        .collect()
+}
+
+/// The Touhou games of the Steam installation at `root`, leaving out the apps in `imported`.
+///
+/// A game is recognized by its app id in `app_ids` or in the title database, by the executables in its
+/// directory, or else by the name in its manifest. Without executables, the item starts it through the Steam client.
+pub fn scan(root: &Path, imported: &HashSet<String>, app_ids: &BTreeMap<String, String>) -> Result<Vec<Discovery>, String> {
+
+    let mut found = vec![];
+
+    for folder in library_folders(root)?.iter() {
+        for app in installed_apps(folder).into_iter().filter(|app| !imported.contains(&app.app_id)) {
+            let known = app_ids.get(&app.app_id).and_then(|id| titles::by_id(id))
+                .or_else(|| titles::by_steam_app_id(&app.app_id));
+            let mut executables = scan::scan(std::slice::from_ref(&app.install_dir));
+
+            if !executables.is_empty() {
+                for discovery in executables.iter_mut() {
+                    discovery.item.actions.push(launch_action(&app.app_id));
+                }
+                found.extend(executables);
+            } else if let Some(title) = known.or_else(|| titles::by_name(&app.name)) {
+                let action = launch_action(&app.app_id);
+                let item = ItemConfig {
+                    name: title.display_name(),
+                    path: action.path,
+                    args: action.args,
+                    runner: action.runner,
+                    ..ItemConfig::default()
+                };
+                found.push(Discovery { group: title.group, title, item });
+            }
+        }
+    }
+
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::utils::testing::{ fixture, scratch_dir };
+
+    #[test]
+    fn reads_library_folders() {
+
+        let root = fixture("steam");
+        let folders = library_folders(&root).unwrap();
+        // the first entry is the root itself, as seen by the machine the fixture was written on.
+        assert_eq!(folders, vec![
+            root.clone(),
+            PathBuf::from("/home/user/.local/share/Steam"),
+            PathBuf::from("/mnt/games/SteamLibrary"),
+        ]);
+
+        // clients before 2021 list the extra folders by index only.
+        let legacy = scratch_dir("steam-legacy");
+        fs::create_dir_all(legacy.join("steamapps")).unwrap();
+        fs::write(legacy.join("steamapps/libraryfolders.vdf"),
+            "\"LibraryFolders\"\n{\n\t\"TimeNextStatsReport\"\t\t\"1589000000\"\n\t\"ContentStatsID\"\t\t\"-123\"\n\t\"1\"\t\t\"/mnt/old\"\n}\n").unwrap();
+        assert_eq!(library_folders(&legacy).unwrap(), vec![legacy.clone(), PathBuf::from("/mnt/old")]);
+
+        let _ = fs::remove_dir_all(&legacy);
+    }
+
+    #[test]
+    fn lists_installed_apps() {
+
+        let root = fixture("steam");
+        let apps: Vec<(String, String, PathBuf)> = installed_apps(&root).into_iter()
+            .map(|app| (app.app_id, app.name, app.install_dir))
+            .collect();
+
+        // the unreadable manifest is skipped.
+        let common = root.join("steamapps/common");
+        assert_eq!(apps, vec![
+            (String::from("1079160"), String::from("Touhou Kikeijuu ~ Wily Beast and Weakest Creature."), common.join("th17")),
+            (String::from("228980"), String::from("Steamworks Common Redistributables"), common.join("Steamworks Shared")),
+            (String::from("745880"), String::from("東方Project 第16弾"), common.join("th16")),
+            (String::from("900002"), String::from("Touhou Kouryuudou ~ Unconnected Marketeers."), common.join("th18")),
+            (String::from("900003"), String::from("Bulletphilia"), common.join("th185")),
+        ]);
+        assert!(installed_apps(Path::new("/nonexistent")).is_empty());
+    }
+
+    #[test]
+    fn scans_touhou_apps() {
+
+        let root = fixture("steam");
+        let app_ids: BTreeMap<String, String> = vec![(String::from("900003"), String::from("th185"))].into_iter().collect();
+
+        let found = scan(&root, &HashSet::new(), &app_ids).unwrap();
+        let summary: Vec<(&str, String, Vec<String>)> = found.iter()
+            .map(|discovery| (discovery.title.id, discovery.item.path.clone(), discovery.item.args.clone()))
+            .collect();
+
+        let th17 = root.join("steamapps/common/th17/th17.exe").to_string_lossy().into_owned();
+        assert_eq!(summary, vec![
+            // found by its executable, with Steam as a secondary action.
+            ("th17", th17, vec![]),
+            // not installed, recognized by the app id of the title database.
+            ("th16", String::from("steam"), vec![String::from("-applaunch"), String::from("745880")]),
+            // not installed, recognized by its name.
+            ("th18", String::from("steam"), vec![String::from("-applaunch"), String::from("900002")]),
+            // recognized by its app id in the setting.
+            ("th185", String::from("steam"), vec![String::from("-applaunch"), String::from("900003")]),
+        ]);
+        assert_eq!(found[0].item.actions[0].args, vec![String::from("-applaunch"), String::from("1079160")]);
+
+        let imported: HashSet<String> = vec!["1079160", "745880", "900002"].into_iter().map(String::from).collect();
+        let found = scan(&root, &imported, &BTreeMap::new()).unwrap();
+        assert!(found.is_empty());
+    }
+}
@@ -0,0 +1,216 @@
+
+use crate::config::tab::ItemConfig;
+use crate::library::GameGroup;
+
+/// A game of the series, as listed by `TITLES`.
+#[derive(Debug)]
+pub struct TouhouTitle {
+
+    /// The id used by the executables and by thcrap, such as `th08` or `th123`.
+    pub id: &'static str,
+    /// The position in the series, such as `12.3`.
+    pub number: &'static str,
+    pub japanese: &'static str,
+    pub romanized: &'static str,
+    pub english: &'static str,
+    pub year: u16,
+    pub group: GameGroup,
+    pub genre: &'static str,
+    /// The executable names of the game, in lowercase.
+    pub executables: &'static [&'static str],
+    /// The last official patch of the game, if it is known.
+    pub latest_version: Option<&'static str>,
+    /// The app id of the game on the Steam store, if it is sold there.
+    pub steam_app_id: Option<u32>,
+}
+
+macro_rules! title {
+    ($id:expr, $number:expr, $japanese:expr, $romanized:expr, $english:expr, $year:expr, $group:ident, $genre:expr, [$($exe:expr),*]) => {
+        title!($id, $number, $japanese, $romanized, $english, $year, $group, $genre, [$($exe),*], None)
+    };
+    ($id:expr, $number:expr, $japanese:expr, $romanized:expr, $english:expr, $year:expr, $group:ident, $genre:expr, [$($exe:expr),*], $latest:expr) => {
+        title!($id, $number, $japanese, $romanized, $english, $year, $group, $genre, [$($exe),*], $latest, None)
+    };
+    ($id:expr, $number:expr, $japanese:expr, $romanized:expr, $english:expr, $year:expr, $group:ident, $genre:expr, [$($exe:expr),*], $latest:expr, $steam:expr) => {
+        TouhouTitle {
+            id: $id, number: $number,
+            japanese: $japanese, romanized: $romanized, english: $english,
+            year: $year, group: GameGroup::$group, genre: $genre,
+            executables: &[$($exe),*],
+            latest_version: $latest,
+            steam_app_id: $steam,
+        }
+    };
+}
+
+pub const TITLES: &[TouhouTitle] = &[
+    title!("th06",  "6",    "東方紅魔郷", "Touhou Koumakyou", "the Embodiment of Scarlet Devil", 2002, Mainline, "Vertical shooter", ["th06.exe", "東方紅魔郷.exe"], Some("v1.02h")),
+    title!("th07",  "7",    "東方妖々夢", "Touhou Youyoumu", "Perfect Cherry Blossom", 2003, Mainline, "Vertical shooter", ["th07.exe"], Some("v1.00b")),
+    title!("th075", "7.5",  "東方萃夢想", "Touhou Suimusou", "Immaterial and Missing Power", 2004, Fighting, "Fighting", ["th075.exe"]),
+    title!("th08",  "8",    "東方永夜抄", "Touhou Eiyashou", "Imperishable Night", 2004, Mainline, "Vertical shooter", ["th08.exe"], Some("v1.00d")),
+    title!("th09",  "9",    "東方花映塚", "Touhou Kaeizuka", "Phantasmagoria of Flower View", 2005, Mainline, "Competitive shooter", ["th09.exe"], Some("v1.50a")),
+    title!("th095", "9.5",  "東方文花帖", "Touhou Bunkachou", "Shoot the Bullet", 2005, SpinOff, "Photography shooter", ["th095.exe"], Some("v1.02a")),
+    title!("th10",  "10",   "東方風神録", "Touhou Fuujinroku", "Mountain of Faith", 2007, Mainline, "Vertical shooter", ["th10.exe"], Some("v1.00a")),
+    title!("th105", "10.5", "東方緋想天", "Touhou Hisouten", "Scarlet Weather Rhapsody", 2008, Fighting, "Fighting", ["th105.exe"]),
+    title!("th11",  "11",   "東方地霊殿", "Touhou Chireiden", "Subterranean Animism", 2008, Mainline, "Vertical shooter", ["th11.exe"], Some("v1.00a")),
+    title!("th12",  "12",   "東方星蓮船", "Touhou Seirensen", "Undefined Fantastic Object", 2009, Mainline, "Vertical shooter", ["th12.exe"], Some("v1.00b")),
+    title!("th123", "12.3", "東方非想天則", "Touhou Hisoutensoku", "Hisoutensoku", 2009, Fighting, "Fighting", ["th123.exe"]),
+    title!("th125", "12.5", "ダブルスポイラー", "Double Spoiler", "Double Spoiler", 2010, SpinOff, "Photography shooter", ["th125.exe"], Some("v1.00a")),
+    title!("th128", "12.8", "妖精大戦争", "Yousei Daisensou", "Great Fairy Wars", 2010, SpinOff, "Vertical shooter", ["th128.exe"], Some("v1.00a")),
+    title!("th13",  "13",   "東方神霊廟", "Touhou Shinreibyou", "Ten Desires", 2011, Mainline, "Vertical shooter", ["th13.exe"], Some("v1.00c")),
+    title!("th135", "13.5", "東方心綺楼", "Touhou Shinkirou", "Hopeless Masquerade", 2013, Fighting, "Fighting", ["th135.exe"]),
+    title!("th14",  "14",   "東方輝針城", "Touhou Kishinjou", "Double Dealing Character", 2013, Mainline, "Vertical shooter", ["th14.exe"], Some("v1.00b")),
+    title!("th143", "14.3", "弾幕アマノジャク", "Danmaku Amanojaku", "Impossible Spell Card", 2014, SpinOff, "Puzzle shooter", ["th143.exe"], Some("v1.00a")),
+    title!("th145", "14.5", "東方深秘録", "Touhou Shinpiroku", "Urban Legend in Limbo", 2015, Fighting, "Fighting", ["th145.exe"]),
+    title!("th15",  "15",   "東方紺珠伝", "Touhou Kanjuden", "Legacy of Lunatic Kingdom", 2015, Mainline, "Vertical shooter", ["th15.exe"], Some("v1.00b")),
+    title!("th155", "15.5", "東方憑依華", "Touhou Hyouibana", "Antinomy of Common Flowers", 2017, Fighting, "Fighting", ["th155.exe"]),
+    title!("th16",  "16",   "東方天空璋", "Touhou Tenkuushou", "Hidden Star in Four Seasons", 2017, Mainline, "Vertical shooter", ["th16.exe"], Some("v1.00a"), Some(745880)),
+    title!("th165", "16.5", "秘封ナイトメアダイアリー", "Hifuu Nightmare Diary", "Violet Detector", 2018, SpinOff, "Photography shooter", ["th165.exe"], Some("v1.00a")),
+    title!("th17",  "17",   "東方鬼形獣", "Touhou Kikeijuu", "Wily Beast and Weakest Creature", 2019, Mainline, "Vertical shooter", ["th17.exe"], Some("v1.00b"), Some(1079160)),
+    title!("th175", "17.5", "東方剛欲異聞", "Touhou Gouyoku Ibun", "Sunken Fossil World", 2021, SpinOff, "Action", ["th175.exe"]),
+    title!("th18",  "18",   "東方虹龍洞", "Touhou Kouryuudou", "Unconnected Marketeers", 2021, Mainline, "Vertical shooter", ["th18.exe"], Some("v1.00a"), Some(1566410)),
+    title!("th185", "18.5", "バレットフィリア達の闇市場", "Bulletphilia-tachi no Yamiichiba", "100th Black Market", 2022, SpinOff, "Vertical shooter", ["th185.exe"], Some("v1.00a")),
+    title!("th19",  "19",   "東方獣王園", "Touhou Juuouen", "Unfinished Dream of All Living Ghost", 2023, Mainline, "Competitive shooter", ["th19.exe"]),
+];
+
+impl TouhouTitle {
+
+    /// The name given to new entries of this game, such as `th08 Imperishable Night`.
+    pub fn display_name(&self) -> String {
+        format!("{} {}", self.id, self.english)
+    }
+
+    /// A line describing the game, shown under its entry.
+    pub fn subtitle(&self) -> String {
+        format!("{} {} ({}, {}) · {}", self.number, self.japanese, self.romanized, self.year, self.genre)
+    }
+
+    /// Sorts the games in release order within the series, `12.3` between `12` and `12.5`.
+    pub fn sort_key(&self) -> u32 {
+
+        let mut parts = self.number.splitn(2, '.');
+        let major: u32 = parts.next().and_then(|part| part.parse().ok()).unwrap_or(0);
+        let minor: u32 = parts.next().and_then(|part| part.parse().ok()).unwrap_or(0);
+        major * 10 + minor
+    }
+}
+
+/// Sort `items` in release order, leaving the unknown games at the end in their current order.
+pub fn sort_items(items: &mut [ItemConfig]) {
+    items.sort_by_key(|item| identify(item).map_or(u32::MAX, TouhouTitle::sort_key));
+}
+
+/// The game sold on Steam as `app_id`.
+pub fn by_steam_app_id(app_id: &str) -> Option<&'static TouhouTitle> {
+
+    let app_id: u32 = app_id.parse().ok()?;
+    TITLES.iter().find(|title| title.steam_app_id == Some(app_id))
+}
+
+pub fn by_id(id: &str) -> Option<&'static TouhouTitle> {
+    TITLES.iter().find(|title| title.id.eq_ignore_ascii_case(id))
+}
+
+/// The game whose executable is called `file_name`, compared in lowercase.
+pub fn by_executable(file_name: &str) -> Option<&'static TouhouTitle> {
+
+    let file_name = file_name.to_lowercase();
+    TITLES.iter().find(|title| title.executables.contains(&file_name.as_str()))
+}
+
+/// The game named in `name`, such as the title of a store page, by its romanized, English or Japanese name.
+pub fn by_name(name: &str) -> Option<&'static TouhouTitle> {
+
+    let name = name.to_lowercase();
+    // the longest match wins, `Touhou Hisoutensoku` also contains `Touhou Hisouten`.
+    TITLES.iter()
+        .flat_map(|title| vec![title.romanized, title.english, title.japanese].into_iter().map(move |part| (title, part)))
+        .filter(|(_, part)| name.contains(&part.to_lowercase()))
+        .max_by_key(|(_, part)| part.chars().count())
+        .map(|(title, _)| title)
+}
+
+/// The game of `item`, from its thcrap game id or the name of its executable.
+pub fn identify(item: &ItemConfig) -> Option<&'static TouhouTitle> {
+
+    item.thcrap.as_ref()
+        .and_then(|thcrap| by_id(&thcrap.game))
+        .or_else(|| {
+            // Windows paths of shared manifests use backslashes.
+            let file_name = item.path.rsplit(['/', '\\']).next()?;
+            by_executable(file_name)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::config::tab::ThcrapConfig;
+
+    fn item(path: &str) -> ItemConfig {
+        ItemConfig { name: String::from(path), path: String::from(path), ..ItemConfig::default() }
+    }
+
+    #[test]
+    fn sorts_in_release_order() {
+
+        let keys: Vec<_> = ["th06", "th075", "th08", "th095", "th10", "th12", "th123", "th125", "th128", "th13", "th19"].iter()
+            .map(|id| by_id(id).unwrap().sort_key())
+            .collect();
+        let mut sorted = keys.clone();
+        sorted.sort();
+        assert_eq!(keys, sorted);
+
+        let mut all: Vec<_> = TITLES.iter().map(TouhouTitle::sort_key).collect();
+        all.dedup();
+        assert_eq!(all.len(), TITLES.len());
+    }
+
+    #[test]
+    fn sorts_spin_offs_between_the_mainline_games_and_unknown_games_last() {
+
+        let mut items = vec![item("/games/th10.exe"), item("/games/custom.exe"), item("D:\\Games\\th095.exe"), item("/games/th08.exe")];
+        let mut patched = item("/thcrap/thcrap_loader.exe");
+        patched.thcrap = Some(ThcrapConfig { dir: String::from("/thcrap"), config: String::from("en"), game: String::from("TH09") });
+        items.push(patched);
+
+        sort_items(&mut items);
+        let paths: Vec<_> = items.iter().map(|item| item.path.as_str()).collect();
+        assert_eq!(paths, vec!["/games/th08.exe", "/thcrap/thcrap_loader.exe", "D:\\Games\\th095.exe", "/games/th10.exe", "/games/custom.exe"]);
+    }
+
+    #[test]
+    fn finds_games_by_name_ignoring_the_case() {
+
+        assert_eq!(by_name("東方Project: touhou kaeizuka ~ PHANTASMAGORIA OF FLOWER VIEW").map(|title| title.id), Some("th09"));
+        assert_eq!(by_name("東方紅魔郷　～ the Embodiment of Scarlet Devil.").map(|title| title.id), Some("th06"));
+        assert_eq!(by_name("Touhou Hisoutensoku").map(|title| title.id), Some("th123"));
+        assert_eq!(by_name("Touhou Hisouten ~ Scarlet Weather Rhapsody").map(|title| title.id), Some("th105"));
+        assert_eq!(by_name("Double Spoiler").map(|title| title.id), Some("th125"));
+        assert!(by_name("Some other shooter").is_none());
+    }
+
+    #[test]
+    fn finds_games_by_executable_and_thcrap_id() {
+
+        assert_eq!(by_executable("TH08.EXE").map(|title| title.id), Some("th08"));
+        assert_eq!(by_executable("東方紅魔郷.exe").map(|title| title.id), Some("th06"));
+        assert_eq!(by_id("TH123").map(|title| title.id), Some("th123"));
+        assert!(by_executable("thcrap_loader.exe").is_none());
+    }
+
+    #[test]
+    fn finds_games_by_steam_app_id() {
+
+        assert_eq!(by_steam_app_id("1079160").map(|title| title.id), Some("th17"));
+        assert!(by_steam_app_id("228980").is_none());
+        assert!(by_steam_app_id("th17").is_none());
+
+        let mut app_ids: Vec<_> = TITLES.iter().filter_map(|title| title.steam_app_id).collect();
+        app_ids.sort();
+        app_ids.dedup();
+        assert_eq!(app_ids.len(), TITLES.iter().filter(|title| title.steam_app_id.is_some()).count());
+    }
+}
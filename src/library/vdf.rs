@@ -0,0 +1,363 @@
+
+use std::convert::TryInto;
+
+/// A value of Valve's text KeyValues format, as in `libraryfolders.vdf` and `appmanifest_*.acf`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Vdf {
+    String(String),
+    /// Keys keep the order of the file and may repeat.
+    Map(Vec<(String, Vdf)>),
+}
+
+impl Vdf {
+
+    /// Parse the root of a file, made of key and value pairs.
+    pub fn parse(text: &str) -> Result<Vdf, String> {
+
+        let mut parser = Parser { chars: text.chars().collect(), pos: 0 };
+        parser.map(true)
+    }
+
+    /// The value of `key`, compared without case like Steam does.
+    pub fn get(&self, key: &str) -> Option<&Vdf> {
+
+        match self {
+            | Vdf::Map(entries) => entries.iter().find(|(k, _)| k.eq_ignore_ascii_case(key)).map(|(_, v)| v),
+            | Vdf::String(_) => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+
+        match self {
+            | Vdf::String(s) => Some(s),
+            | Vdf::Map(_) => None,
+        }
+    }
+
+    pub fn entries(&self) -> &[(String, Vdf)] {
+
+        match self {
+            | Vdf::Map(entries) => entries,
+            | Vdf::String(_) => &[],
+        }
+    }
+}
+
+struct Parser {
+
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+
+    fn error(&self, message: &str) -> String {
+        format!("{} at character {}", message, self.pos)
+    }
+
+    /// Skip whitespace and `//` comments.
+    fn skip_blank(&mut self) {
+
+        loop {
+            match self.chars.get(self.pos) {
+                | Some(ch) if ch.is_whitespace() || *ch == '\u{feff}' => self.pos += 1,
+                | Some('/') if self.chars.get(self.pos + 1) == Some(&'/') => {
+                    while self.chars.get(self.pos).is_some_and(|ch| *ch != '\n') {
+                        self.pos += 1;
+                    }
+                },
+                | _ => return,
+            }
+        }
+    }
+
+    fn map(&mut self, is_root: bool) -> Result<Vdf, String> {
+
+        let mut entries = vec![];
+
+        loop {
+            self.skip_blank();
+            match self.chars.get(self.pos) {
+                | None if is_root => return Ok(Vdf::Map(entries)),
+                | None => return Err(self.error("missing `}`")),
+                | Some('}') if !is_root => {
+                    self.pos += 1;
+                    return Ok(Vdf::Map(entries))
+                },
+                | Some('}') => return Err(self.error("unexpected `}`")),
+                | Some(_) => {
+                    let key = self.token()?;
+                    self.skip_blank();
+                    let value = if self.chars.get(self.pos) == Some(&'{') {
+                        self.pos += 1;
+                        self.map(false)?
+                    } else {
+                        Vdf::String(self.token()?)
+                    };
+                    entries.push((key, value));
+                    self.skip_conditional();
+                },
+            }
+        }
+    }
+
+    /// Skip the platform conditions such as `[$WIN32]` that may follow a value.
+    fn skip_conditional(&mut self) {
+
+        let start = self.pos;
+        self.skip_blank();
+        if self.chars.get(self.pos) == Some(&'[') {
+            while self.chars.get(self.pos).is_some_and(|ch| *ch != ']') {
+                self.pos += 1;
+            }
+            self.pos += 1;
+        } else {
+            self.pos = start;
+        }
+    }
+
+    /// A quoted string with escapes, or a bare word.
+    fn token(&mut self) -> Result<String, String> {
+
+        let mut token = String::new();
+
+        if self.chars.get(self.pos) == Some(&'"') {
+            self.pos += 1;
+            loop {
+                let ch = *self.chars.get(self.pos).ok_or_else(|| self.error("unterminated string"))?;
+                self.pos += 1;
+                match ch {
+                    | '"' => return Ok(token),
+                    | '\\' => {
+                        let escaped = *self.chars.get(self.pos).ok_or_else(|| self.error("unterminated string"))?;
+                        self.pos += 1;
+                        token.push(match escaped {
+                            | 'n' => '\n',
+                            | 't' => '\t',
+                            | other => other,
+                        });
+                    },
+                    | ch => token.push(ch),
+                }
+            }
+        }
+
+        while let Some(&ch) = self.chars.get(self.pos) {
+            if ch.is_whitespace() || ch == '{' || ch == '}' || ch == '"' {
+                break
+            }
+            token.push(ch);
+            self.pos += 1;
+        }
+
+        if token.is_empty() {
+            Err(self.error("expected a key or a value"))
+        } else {
+            Ok(token)
+        }
+    }
+}
+
+/// A value of Valve's binary KeyValues format, as in `shortcuts.vdf`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BinaryVdf {
+    Map(Vec<(String, BinaryVdf)>),
+    String(String),
+    Int32(i32),
+    Float32(f32),
+    UInt64(u64),
+}
+
+const TYPE_MAP: u8     = 0x00;
+const TYPE_STRING: u8  = 0x01;
+const TYPE_INT32: u8   = 0x02;
+const TYPE_FLOAT32: u8 = 0x03;
+const TYPE_UINT64: u8  = 0x07;
+const TYPE_END: u8     = 0x08;
+
+impl BinaryVdf {
+
+    /// Decode the entries of a file, up to its closing end marker.
+    pub fn decode(bytes: &[u8]) -> Result<BinaryVdf, String> {
+
+        let mut pos = 0;
+        let root = decode_map(bytes, &mut pos)?;
+        if pos != bytes.len() {
+            return Err(format!("unexpected data after byte {}", pos))
+        }
+        Ok(root)
+    }
+
+    /// Encode the entries of a map, as the whole of a file. Other values encode to nothing.
+    pub fn encode(&self) -> Vec<u8> {
+
+        let mut bytes = vec![];
+        if let BinaryVdf::Map(entries) = self {
+            encode_map(entries, &mut bytes);
+        }
+        bytes
+    }
+
+    /// The value of `key`, compared without case, as Steam writes both `AppName` and `appname`.
+    pub fn get(&self, key: &str) -> Option<&BinaryVdf> {
+
+        match self {
+            | BinaryVdf::Map(entries) => entries.iter().find(|(k, _)| k.eq_ignore_ascii_case(key)).map(|(_, v)| v),
+            | _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+
+        match self {
+            | BinaryVdf::String(s) => Some(s),
+            | _ => None,
+        }
+    }
+}
+
+fn decode_map(bytes: &[u8], pos: &mut usize) -> Result<BinaryVdf, String> {
+
+    let mut entries = vec![];
+
+    loop {
+        let kind = *bytes.get(*pos).ok_or_else(|| format!("missing end of map at byte {}", *pos))?;
+        *pos += 1;
+        if kind == TYPE_END {
+            return Ok(BinaryVdf::Map(entries))
+        }
+
+        let key = decode_string(bytes, pos)?;
+        let value = match kind {
+            | TYPE_MAP     => decode_map(bytes, pos)?,
+            | TYPE_STRING  => BinaryVdf::String(decode_string(bytes, pos)?),
+            | TYPE_INT32   => BinaryVdf::Int32(i32::from_le_bytes(decode_array(bytes, pos)?)),
+            | TYPE_FLOAT32 => BinaryVdf::Float32(f32::from_le_bytes(decode_array(bytes, pos)?)),
+            | TYPE_UINT64  => BinaryVdf::UInt64(u64::from_le_bytes(decode_array(bytes, pos)?)),
+            | other => return Err(format!("unknown value type {:#04x} at byte {}", other, *pos - 1)),
+        };
+        entries.push((key, value));
+    }
+}
+
+fn decode_string(bytes: &[u8], pos: &mut usize) -> Result<String, String> {
+
+    let length = bytes[*pos..].iter().position(|byte| *byte == 0)
+        .ok_or_else(|| format!("unterminated string at byte {}", *pos))?;
+    let string = String::from_utf8(bytes[*pos..*pos + length].to_vec())
+        .map_err(|_| format!("invalid UTF-8 string at byte {}", *pos))?;
+    *pos += length + 1;
+    Ok(string)
+}
+
+fn decode_array<const N: usize>(bytes: &[u8], pos: &mut usize) -> Result<[u8; N], String> {
+
+    let array = bytes.get(*pos..*pos + N)
+        .and_then(|slice| slice.try_into().ok())
+        .ok_or_else(|| format!("truncated number at byte {}", *pos))?;
+    *pos += N;
+    Ok(array)
+}
+
+fn encode_map(entries: &[(String, BinaryVdf)], bytes: &mut Vec<u8>) {
+
+    for (key, value) in entries.iter() {
+        let kind = match value {
+            | BinaryVdf::Map(_)     => TYPE_MAP,
+            | BinaryVdf::String(_)  => TYPE_STRING,
+            | BinaryVdf::Int32(_)   => TYPE_INT32,
+            | BinaryVdf::Float32(_) => TYPE_FLOAT32,
+            | BinaryVdf::UInt64(_)  => TYPE_UINT64,
+        };
+        bytes.push(kind);
+        encode_string(key, bytes);
+
+        match value {
+            | BinaryVdf::Map(entries) => encode_map(entries, bytes),
+            | BinaryVdf::String(s)    => encode_string(s, bytes),
+            | BinaryVdf::Int32(n)     => bytes.extend_from_slice(&n.to_le_bytes()),
+            | BinaryVdf::Float32(n)   => bytes.extend_from_slice(&n.to_le_bytes()),
+            | BinaryVdf::UInt64(n)    => bytes.extend_from_slice(&n.to_le_bytes()),
+        }
+    }
+    bytes.push(TYPE_END);
+}
+
+fn encode_string(s: &str, bytes: &mut Vec<u8>) {
+
+    bytes.extend_from_slice(s.as_bytes());
+    bytes.push(0);
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::fs;
+
+    use crate::utils::testing::fixture;
+
+    #[test]
+    fn binary_round_trip() {
+
+        let shortcut = BinaryVdf::Map(vec![
+            (String::from("appid"), BinaryVdf::Int32(-1_234_567_890)),
+            (String::from("AppName"), BinaryVdf::String(String::from("東方永夜抄"))),
+            (String::from("Exe"), BinaryVdf::String(String::from("\"/usr/bin/th-launcher\""))),
+            (String::from("LastPlayTime"), BinaryVdf::Int32(0)),
+            (String::from("Ratio"), BinaryVdf::Float32(1.5)),
+            (String::from("Id64"), BinaryVdf::UInt64(76_561_198_000_000_000)),
+            (String::from("tags"), BinaryVdf::Map(vec![(String::from("0"), BinaryVdf::String(String::from("Mainline")))])),
+        ]);
+        let root = BinaryVdf::Map(vec![
+            (String::from("shortcuts"), BinaryVdf::Map(vec![(String::from("0"), shortcut), (String::from("1"), BinaryVdf::Map(vec![]))])),
+        ]);
+
+        let bytes = root.encode();
+        assert_eq!(&bytes[..11], b"\x00shortcuts\x00");
+        assert_eq!(&bytes[bytes.len() - 3..], b"\x08\x08\x08");
+        assert_eq!(BinaryVdf::decode(&bytes).unwrap(), root);
+    }
+
+    #[test]
+    fn binary_file_is_encoded_back_byte_for_byte() {
+
+        let bytes = fs::read(fixture("steam/userdata/10000001/config/shortcuts.vdf")).unwrap();
+        let root = BinaryVdf::decode(&bytes).unwrap();
+
+        let names: Vec<&str> = ["0", "1"].iter()
+            .filter_map(|index| root.get("shortcuts")?.get(index)?.get("appname")?.as_str())
+            .collect();
+        assert_eq!(names, vec!["RetroArch", "東方妖々夢"]);
+        assert_eq!(root.encode(), bytes);
+    }
+
+    #[test]
+    fn binary_rejects_truncated_files() {
+
+        let bytes = fs::read(fixture("steam/userdata/10000001/config/shortcuts.vdf")).unwrap();
+
+        for end in [1, 11, 30, bytes.len() - 1].iter() {
+            assert!(BinaryVdf::decode(&bytes[..*end]).is_err(), "decoded the first {} bytes", end);
+        }
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(BinaryVdf::decode(&trailing).is_err());
+        assert!(BinaryVdf::decode(b"\x09key\x00\x08").is_err());
+    }
+
+    #[test]
+    fn text_handles_comments_conditionals_and_escapes() {
+
+        let text = "// written by Steam\n\"AppState\"\n{\n\t\"appid\"\t\t\"228980\" [$WIN32]\n\t\"name\"\t\"a \\\"b\\\" c\\\\\"\n\t\"UserConfig\" { \"language\" \"english\" }\n}\n";
+        let vdf = Vdf::parse(text).unwrap();
+        let state = vdf.get("appstate").unwrap();
+
+        assert_eq!(state.get("AppID").and_then(Vdf::as_str), Some("228980"));
+        assert_eq!(state.get("name").and_then(Vdf::as_str), Some("a \"b\" c\\"));
+        assert_eq!(state.get("UserConfig").and_then(|config| config.get("language")).and_then(Vdf::as_str), Some("english"));
+        assert!(Vdf::parse("\"AppState\" {").is_err());
+    }
+}
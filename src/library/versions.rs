@@ -0,0 +1,261 @@
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{ Path, PathBuf };
+use std::sync::mpsc::{ self, Receiver };
+use std::thread;
+
+use crate::config::manifest::VERSIONS_FILE_NAME;
+use crate::config::tab::TabConfig;
+use crate::library::sha256;
+use crate::library::titles::{ self, TouhouTitle };
+use crate::thcrap;
+use crate::thcrap::json::Json;
+
+const BUNDLED_VERSIONS: &str = include_str!("versions.txt");
+
+#[derive(Debug, Clone)]
+pub struct GameVersion {
+
+    pub id: String,
+    pub version: String,
+    /// Such as `original`, or the name of a fan-made build.
+    pub build: String,
+}
+
+/// Identifies the executables of the games by their SHA-256.
+#[derive(Debug, Default)]
+pub struct VersionTable {
+
+    versions: HashMap<String, GameVersion>,
+}
+
+/// What is known about an executable.
+pub struct VersionCheck {
+
+    pub sha256: String,
+    pub version: Option<GameVersion>,
+    pub warnings: Vec<String>,
+}
+
+impl VersionTable {
+
+    /// Read the bundled table, the `versions.js` of the thcrap installations used in `tabs`
+    /// and the table of the user, which wins over the others.
+    pub fn load(tabs: &[TabConfig]) -> VersionTable {
+
+        let mut table = VersionTable::default();
+        table.read_lines(BUNDLED_VERSIONS);
+
+        let thcrap_dirs = tabs.iter()
+            .flat_map(|tab| tab.items.iter())
+            .flat_map(|item| item.thcrap.iter().chain(item.variants.iter().filter_map(|variant| variant.thcrap.as_ref())))
+            .map(|thcrap| thcrap.dir.clone());
+        for dir in thcrap_dirs {
+            table.read_thcrap(Path::new(&dir));
+        }
+
+        if let Ok(lines) = env::current_dir().and_then(|cwd| fs::read_to_string(cwd.join(VERSIONS_FILE_NAME))) {
+            table.read_lines(&lines);
+        }
+
+        table
+    }
+
+    fn read_lines(&mut self, lines: &str) {
+
+        for line in lines.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if words.len() >= 3 {
+                let version = GameVersion {
+                    id: words[1].to_owned(),
+                    version: words[2].to_owned(),
+                    build: words.get(3).cloned().unwrap_or("original").to_owned(),
+                };
+                self.versions.insert(words[0].to_lowercase(), version);
+            }
+        }
+    }
+
+    /// thcrap lists the builds it supports in `versions.js`, as `"hashes": { "<sha256>": [id, version, build] }`.
+    fn read_thcrap(&mut self, dir: &Path) {
+
+        let candidates = [dir.join("versions.js"), dir.join("repos/nmlgc/base_tsa/versions.js"), dir.join("repos/thpatch/thcrap/versions.js")];
+
+        for path in candidates.iter().filter(|path| path.is_file()) {
+            let hashes = match thcrap::read_json(path).ok().and_then(|json| json.get("hashes").cloned()) {
+                | Some(Json::Object(hashes)) => hashes,
+                | _ => continue,
+            };
+
+            for (sha256, entry) in hashes.iter() {
+                let fields: Vec<&str> = entry.as_array()
+                    .map(|fields| fields.iter().filter_map(Json::as_str).collect())
+                    .unwrap_or_default();
+
+                if fields.len() >= 2 {
+                    let version = GameVersion {
+                        id: fields[0].to_owned(),
+                        version: fields[1].to_owned(),
+                        build: fields.get(2).cloned().unwrap_or("original").to_owned(),
+                    };
+                    self.versions.entry(sha256.to_lowercase()).or_insert(version);
+                }
+            }
+        }
+    }
+
+    /// Hash the executable at `path` and look it up, expecting the game `expected` if it is known.
+    pub fn check(&self, path: &Path, expected: Option<&TouhouTitle>) -> Result<VersionCheck, String> {
+
+        let sha256 = sha256::hash_file(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let version = self.versions.get(&sha256).cloned();
+
+        let mut warnings = vec![];
+        match version {
+            | Some(ref version) => {
+                if let Some(expected) = expected.filter(|expected| !expected.id.eq_ignore_ascii_case(&version.id)) {
+                    warnings.push(format!("this is the executable of {}, not {}", version.id, expected.id));
+                }
+
+                let latest = titles::by_id(&version.id).and_then(|title| title.latest_version);
+                if let Some(latest) = latest.filter(|latest| !version.version.eq_ignore_ascii_case(latest)) {
+                    warnings.push(format!("outdated: {} is not the latest {}", version.version, latest));
+                }
+                if version.build != "original" {
+                    warnings.push(format!("not an official build: {}", version.build));
+                }
+            },
+            | None => warnings.push(String::from("unknown version, the executable may be modified or from an unlisted release")),
+        }
+
+        Ok(VersionCheck { sha256, version, warnings })
+    }
+}
+
+/// The version of an executable, looked up on a background thread since hashing a game on a slow
+/// drive keeps the interface from responding.
+pub struct VersionLookup {
+
+    receiver: Receiver<Result<VersionCheck, String>>,
+}
+
+impl VersionLookup {
+
+    pub fn start(tabs: Vec<TabConfig>, path: PathBuf, expected: Option<&'static TouhouTitle>) -> VersionLookup {
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(VersionTable::load(&tabs).check(&path, expected));
+        });
+
+        VersionLookup { receiver }
+    }
+
+    /// What is known about the executable, once it is hashed.
+    pub fn poll(&self) -> Option<Result<VersionCheck, String>> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::time::Duration;
+
+    use crate::utils::testing::scratch_dir;
+
+    // the SHA-256 of `abc`, the content of the executables written by the tests.
+    const ABC: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    fn with_lines(lines: &str) -> VersionTable {
+
+        let mut table = VersionTable::default();
+        table.read_lines(BUNDLED_VERSIONS);
+        table.read_lines(lines);
+        table
+    }
+
+    #[test]
+    fn the_bundled_table_is_well_formed() {
+
+        for line in BUNDLED_VERSIONS.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            assert!(words.len() >= 3 && words.len() <= 4, "{:?} is not `<sha256> <game id> <version> [build]`", line);
+            assert!(words[0].len() == 64 && words[0].chars().all(|ch| ch.is_ascii_hexdigit()), "{:?} is not a SHA-256", words[0]);
+            assert!(titles::by_id(words[1]).is_some(), "{:?} is not a known game", words[1]);
+        }
+    }
+
+    #[test]
+    fn looks_executables_up_by_their_hash() {
+
+        let dir = scratch_dir("versions-lookup");
+        let path = dir.join("th08.exe");
+        fs::write(&path, "abc").unwrap();
+
+        let table = with_lines(&format!("# a comment\n{} th08 v1.00d\n", ABC.to_uppercase()));
+        let check = table.check(&path, titles::by_id("th08")).unwrap();
+        assert_eq!(check.sha256, ABC);
+        assert_eq!(check.version.map(|version| (version.id, version.version, version.build)),
+            Some((String::from("th08"), String::from("v1.00d"), String::from("original"))));
+        assert!(check.warnings.is_empty());
+
+        let table = with_lines(&format!("{} th07 v1.00a fan-translation\n", ABC));
+        let check = table.check(&path, titles::by_id("th08")).unwrap();
+        assert_eq!(check.warnings, vec![
+            "this is the executable of th07, not th08",
+            "outdated: v1.00a is not the latest v1.00b",
+            "not an official build: fan-translation",
+        ]);
+
+        let check = VersionTable::default().check(&path, None).unwrap();
+        assert!(check.version.is_none());
+        assert_eq!(check.warnings.len(), 1);
+        assert!(VersionTable::default().check(&dir.join("missing.exe"), None).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn reads_the_versions_of_thcrap() {
+
+        let dir = scratch_dir("versions-thcrap");
+        fs::write(dir.join("versions.js"), format!("{{\n\t\"hashes\": {{\n\t\t\"{}\": [\"th08\", \"v1.00d\"],\n\t\t\"bad\": 3\n\t}}\n}}\n", ABC)).unwrap();
+        fs::write(dir.join("th08.exe"), "abc").unwrap();
+
+        let mut table = with_lines(&format!("{} th08 v1.00d custom\n", ABC));
+        table.read_thcrap(&dir);
+        // the table of the user wins over thcrap.
+        assert_eq!(table.check(&dir.join("th08.exe"), None).unwrap().version.unwrap().build, "custom");
+
+        let mut table = VersionTable::default();
+        table.read_thcrap(&dir);
+        assert_eq!(table.check(&dir.join("th08.exe"), None).unwrap().version.unwrap().build, "original");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn looks_versions_up_in_the_background() {
+
+        let dir = scratch_dir("versions-background");
+        let path = dir.join("th08.exe");
+        fs::write(&path, "abc").unwrap();
+
+        let lookup = VersionLookup::start(vec![], path, titles::by_id("th08"));
+        let check = loop {
+            match lookup.poll() {
+                | Some(check) => break check,
+                | None => thread::sleep(Duration::from_millis(10)),
+            }
+        };
+        assert_eq!(check.unwrap().sha256, ABC);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,139 @@
+
+use std::env;
+use std::fs;
+use std::path::{ Path, PathBuf };
+
+use crate::library::scan::{ self, Discovery };
+
+/// The prefixes searched when none is configured, below the home directory.
+const DEFAULT_PREFIXES: &[&str] = &[".wine"];
+
+/// The configured prefixes, or else the default and the managed ones that exist,
+/// and the problems of the configured ones that were skipped.
+pub fn find_prefixes(configured: &[String], managed_dir: &Path) -> (Vec<PathBuf>, Vec<String>) {
+
+    if !configured.is_empty() {
+        let (prefixes, missing): (Vec<PathBuf>, Vec<PathBuf>) = configured.iter()
+            .map(PathBuf::from)
+            .partition(|prefix| prefix.join("drive_c").is_dir());
+        let warnings = missing.iter()
+            .map(|prefix| format!("{} is not a Wine prefix: it has no drive_c", prefix.display()))
+            .collect();
+        return (prefixes, warnings)
+    }
+
+    let defaults = env::var_os("HOME").map(PathBuf::from).into_iter()
+        .flat_map(|home| DEFAULT_PREFIXES.iter().map(move |prefix| home.join(prefix)));
+    let mut managed: Vec<PathBuf> = fs::read_dir(managed_dir).into_iter()
+        .flat_map(|entries| entries.filter_map(Result::ok).map(|entry| entry.path()))
+        .collect();
+    managed.sort();
+
+    let prefixes = defaults.chain(managed)
+        .filter(|prefix| prefix.join("drive_c").is_dir())
+        .collect();
+    (prefixes, vec![])
+}
+
+/// The games installed in the `drive_c` of `prefixes`, started by `wine` in their prefix.
+///
+/// The prefixes in `managed_dir` are referred to by their name, as `wine_prefix`,
+/// the others are set as `WINEPREFIX`. The prefixes whose `drive_c` cannot be read are skipped
+/// and their problems returned.
+pub fn discover(prefixes: &[PathBuf], managed_dir: &Path) -> (Vec<Discovery>, Vec<String>) {
+
+    let mut found = vec![];
+    let mut warnings = vec![];
+
+    for prefix in prefixes.iter() {
+        let entries = match fs::read_dir(prefix.join("drive_c")) {
+            | Ok(entries) => entries,
+            | Err(e) => {
+                warnings.push(format!("{} is not a Wine prefix: {}", prefix.display(), e));
+                continue
+            },
+        };
+
+        // the system files of Windows hold no game, and are the bulk of a prefix.
+        let roots: Vec<PathBuf> = entries.filter_map(Result::ok)
+            .filter(|entry| !entry.file_name().to_string_lossy().eq_ignore_ascii_case("windows"))
+            .map(|entry| entry.path())
+            .collect();
+
+        for mut discovery in scan::scan(&roots).into_iter() {
+            let item = &mut discovery.item;
+            item.runner = Some(String::from("wine"));
+
+            match prefix.file_name() {
+                | Some(name) if prefix.parent() == Some(managed_dir) => {
+                    item.wine_prefix = Some(name.to_string_lossy().into_owned());
+                },
+                | _ => {
+                    item.env.insert(String::from("WINEPREFIX"), prefix.to_string_lossy().into_owned());
+                },
+            }
+            found.push(discovery);
+        }
+    }
+
+    (found, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::utils::testing::scratch_dir;
+
+    fn install(prefix: &Path, path: &str) {
+
+        let exe = prefix.join("drive_c").join(path);
+        fs::create_dir_all(exe.parent().unwrap()).unwrap();
+        fs::write(exe, b"MZ").unwrap();
+    }
+
+    #[test]
+    fn skips_the_configured_prefixes_without_drive_c() {
+
+        let dir = scratch_dir("wineprefix-find");
+        install(&dir.join("touhou"), "Games/th08/th08.exe");
+        fs::create_dir_all(dir.join("typo")).unwrap();
+
+        let configured = vec![
+            dir.join("touhou").to_string_lossy().into_owned(),
+            dir.join("typo").to_string_lossy().into_owned(),
+        ];
+        let (prefixes, warnings) = find_prefixes(&configured, &dir.join("managed"));
+
+        assert_eq!(prefixes, [dir.join("touhou")]);
+        assert_eq!(warnings, [format!("{} is not a Wine prefix: it has no drive_c", dir.join("typo").display())]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn discovers_the_games_of_the_readable_prefixes() {
+
+        let dir = scratch_dir("wineprefix-discover");
+        let managed_dir = dir.join("managed");
+        install(&managed_dir.join("eosd"), "Program Files/th06/th06.exe");
+        install(&dir.join("touhou"), "Games/th08/th08.exe");
+        install(&dir.join("touhou"), "windows/system32/th07.exe");
+
+        let prefixes = vec![managed_dir.join("eosd"), dir.join("gone"), dir.join("touhou")];
+        let (found, warnings) = discover(&prefixes, &managed_dir);
+
+        let ids: Vec<&str> = found.iter().map(|discovery| discovery.title.id).collect();
+        assert_eq!(ids, ["th06", "th08"]);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with(&format!("{} is not a Wine prefix: ", dir.join("gone").display())));
+
+        let (eosd, th08) = (&found[0].item, &found[1].item);
+        assert_eq!(eosd.wine_prefix.as_deref(), Some("eosd"));
+        assert!(eosd.env.is_empty());
+        assert_eq!(th08.wine_prefix, None);
+        assert_eq!(th08.env.get("WINEPREFIX"), Some(&dir.join("touhou").to_string_lossy().into_owned()));
+        assert_eq!(th08.runner.as_deref(), Some("wine"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -5,6 +5,10 @@ extern crate serde_derive;
 mod utils;
 mod scene;
 mod config;
+mod launch;
+mod cli;
+mod thcrap;
+mod library;
 
 use termion::raw::{ IntoRawMode, RawTerminal };
 use termion::input::MouseTerminal;
@@ -14,10 +18,21 @@ use tui::Terminal;
 use tui::terminal::Frame;
 use tui::backend::TermionBackend;
 
+use crate::cli::CliCommand;
 use crate::config::EngineConfig;
+use crate::config::tab::{ TabConfig, ItemConfig };
+use crate::launch::{ Launcher, LaunchOutcome, LaunchReport };
+use crate::launch::command::LaunchCommand;
+use crate::launch::winpath::PathMap;
+use crate::library::{ desktop, scan };
 use crate::scene::{ EventNerve, SceneAction };
 
+use std::collections::HashSet;
+use std::env;
+use std::fs;
 use std::io;
+use std::path::{ Path, PathBuf };
+use std::thread;
 
 type THLError     = Result<(), failure::Error>;
 type THLBackend   = TermionBackend<AlternateScreen<MouseTerminal<RawTerminal<io::Stdout>>>>;
@@ -39,7 +54,7 @@ fn init_terminal() -> Result<DstTerminal, failure::Error> {
 
 fn main_loop(terminal: &mut DstTerminal, config: &mut EngineConfig) -> THLError {
 
-    let mut thl_scene = scene::THLScene::new(config.tabs.clone());
+    let mut thl_scene = scene::THLScene::new(config.tabs.clone(), config.setting.clone());
     let mut event_dispatch = EventNerve::new(config.setting.clone());
 
     loop {
@@ -61,15 +76,173 @@ fn main_loop(terminal: &mut DstTerminal, config: &mut EngineConfig) -> THLError
     Ok(())
 }
 
+/// Print what launching `selector` would execute.
+fn dry_run(config: &EngineConfig, selector: &str) -> THLError {
+
+    let (tab, item) = config.tabs.find_item(selector)
+        .ok_or_else(|| failure::err_msg(format!("No game matches `{}`.", selector)))?;
+
+    // not through `Launcher`, which opens the session log: a dry run must leave the disk alone.
+    let command = LaunchCommand::compose(&config.setting, tab, &item.resolve_variant());
+
+    println!("[{}] {}", tab.name, item.name);
+    for line in command.preview() {
+        println!("{}", line);
+    }
+
+    Ok(())
+}
+
+/// Launch `selector` without the terminal interface, waiting for it and its restarts to end.
+fn run(config: &EngineConfig, selector: &str) -> THLError {
+
+    let (tab, item) = config.tabs.find_item(selector)
+        .ok_or_else(|| failure::err_msg(format!("No game matches `{}`.", selector)))?;
+
+    let mut launcher = Launcher::new(config.setting.clone());
+    let mut is_failed = false;
+
+    match launcher.launch(tab, item) {
+        | LaunchOutcome::Started { warnings, .. } => {
+            for warning in warnings.iter() {
+                eprintln!("warning: {}", warning);
+            }
+        },
+        | LaunchOutcome::AlreadyRunning { pid } => {
+            return Err(failure::err_msg(format!("{} is already running with pid {}.", item.name, pid)))
+        },
+        | LaunchOutcome::Finished(report) => is_failed |= print_report(&report),
+    }
+
+    while !launcher.is_idle() {
+        thread::sleep(config.setting.tick_rate);
+        for report in launcher.poll() {
+            is_failed |= print_report(&report);
+        }
+    }
+
+    if is_failed {
+        Err(failure::err_msg(format!("{} did not run successfully.", item.name)))
+    } else {
+        Ok(())
+    }
+}
+
+/// Print the problems of `report` on the standard error, returning whether the game failed.
+fn print_report(report: &LaunchReport) -> bool {
+
+    for warning in report.warnings.iter() {
+        eprintln!("warning: {}", warning);
+    }
+    if let Some(ref failure) = report.failure {
+        eprintln!("error  : {}", failure);
+    }
+    report.failure.is_some()
+}
+
+/// Write the desktop entries of the game or of the tab `selector`.
+fn desktop_add(config: &EngineConfig, selector: &str) -> THLError {
+
+    let entries: Vec<(&TabConfig, &ItemConfig)> = match config.tabs.find_item(selector) {
+        | Some(entry) => vec![entry],
+        | None => config.tabs.find_tab(selector)
+            .map(|tab| tab.items.iter().map(|item| (tab, item)).collect())
+            .ok_or_else(|| failure::err_msg(format!("No game or tab matches `{}`.", selector)))?,
+    };
+
+    let dir = desktop::applications_dir()
+        .ok_or_else(|| failure::err_msg("Neither XDG_DATA_HOME nor HOME is set."))?;
+    let launcher = env::current_exe()?;
+    let manifest_dir = env::current_dir()?;
+
+    for (tab, item) in entries.into_iter() {
+        let path = desktop::write_entry(&dir, tab, item, &launcher, &manifest_dir)?;
+        println!("wrote {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Remove the desktop entries written for `selector`, a game or a tab, or all of them.
+fn desktop_remove(config: &EngineConfig, selector: Option<&str>) -> THLError {
+
+    let dir = desktop::applications_dir()
+        .ok_or_else(|| failure::err_msg("Neither XDG_DATA_HOME nor HOME is set."))?;
+
+    let is_wanted = |id: &str| match selector {
+        | None => true,
+        | Some(selector) => match config.tabs.find_item(selector) {
+            | Some((tab, item)) => desktop::entry_id(tab, item) == id,
+            // the game may be gone from the manifest already.
+            | None => id.eq_ignore_ascii_case(selector)
+                || id.split('/').next().is_some_and(|tab| tab.eq_ignore_ascii_case(selector.trim_end_matches('/'))),
+        },
+    };
+
+    for (path, _) in desktop::generated_entries(&dir).into_iter().filter(|(_, id)| is_wanted(id)) {
+        fs::remove_file(&path)?;
+        println!("removed {}", path.display());
+    }
+
+    Ok(())
+}
+
+/// Add the games of the desktop entries in `dirs` that are not in the library yet.
+fn desktop_import(config: &mut EngineConfig, dirs: &[String]) -> THLError {
+
+    let dirs: Vec<PathBuf> = if dirs.is_empty() {
+        desktop::default_import_dirs()
+    } else {
+        dirs.iter().map(PathBuf::from).collect()
+    };
+
+    let path_map = PathMap::from_setting(&config.setting);
+    let known: HashSet<PathBuf> = config.tabs.tabs.iter()
+        .flat_map(|tab| tab.items.iter())
+        .map(|item| PathBuf::from(path_map.to_unix(&item.path)))
+        .collect();
+
+    let discoveries = scan::arrange(desktop::import(&dirs), &known);
+    for discovery in discoveries.iter() {
+        println!("[{}] {} ({})", discovery.group.tab_name(), discovery.item.name, discovery.item.path);
+    }
+    println!("imported {} games", discoveries.len());
+
+    let ops = scan::into_ops(discoveries, &config.tabs.tabs);
+    config.update(ops)
+}
+
+/// The manifest found from `dir`, or else the default one, written to `dir` unless it is for a dry run.
+fn load_config(dir: &Path, is_dry_run: bool) -> Result<EngineConfig, failure::Error> {
+
+    if let Some(config) = EngineConfig::init(dir) {
+        return Ok(config)
+    }
+
+    let config = EngineConfig::default();
+    // a dry run must leave the disk alone.
+    if !is_dry_run {
+        config.write_manifest_in(dir)?;
+    }
+    Ok(config)
+}
+
 fn main() -> THLError {
 
+    let command = CliCommand::from_env()?;
+
     // Read configuration.
-    let mut config = EngineConfig::init().unwrap_or_else(|| {
-        let config = EngineConfig::default();
-        config.write_manifest()
-            .expect("Failed to write manifest content.");
-        config
-    });
+    let is_dry_run = matches!(command, CliCommand::DryRun { .. });
+    let mut config = load_config(&env::current_dir()?, is_dry_run)?;
+
+    match command {
+        | CliCommand::Interactive => {},
+        | CliCommand::DryRun { selector } => return dry_run(&config, &selector),
+        | CliCommand::Run { selector } => return run(&config, &selector),
+        | CliCommand::DesktopAdd { selector } => return desktop_add(&config, &selector),
+        | CliCommand::DesktopRemove { selector } => return desktop_remove(&config, selector.as_deref()),
+        | CliCommand::DesktopImport { dirs } => return desktop_import(&mut config, &dirs),
+    }
 
     // Terminal initialization.
     let mut terminal = init_terminal()?;
@@ -78,3 +251,25 @@ fn main() -> THLError {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::utils::testing::scratch_dir;
+
+    #[test]
+    fn dry_runs_leave_an_empty_directory_alone() {
+
+        let dir = scratch_dir("main-dry-run");
+
+        let config = load_config(&dir, true).unwrap();
+        assert!(dry_run(&config, "Default/th08").is_err());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        load_config(&dir, false).unwrap();
+        assert!(dir.join("thl.toml").is_file());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
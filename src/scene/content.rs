@@ -1,14 +1,13 @@
 
 use tui::layout::{ Rect, Alignment };
 use tui::style::{ Style, Color, Modifier };
-use tui::widgets::{ Block, Borders, Paragraph, SelectableList, Text, Widget };
+use tui::widgets::{ Block, Borders, List, Paragraph, SelectableList, Text, Widget };
 
 use crate::scene::TerminalPainter;
 use crate::config::ConfigOp;
 use crate::config::tab::{ TabsConfig, TabConfig, ItemConfig };
-
-use std::process::{ Command, ExitStatus };
-use std::io::Error;
+use crate::library::health::PathStatus;
+use crate::library::titles;
 
 pub struct ContentPainter {
 
@@ -16,10 +15,16 @@ pub struct ContentPainter {
     current_tab: usize,
 
     tabs: Vec<TabConfig>,
+    /// The variants of the selected game, while they are being picked.
+    variant_picker: Option<ListState>,
+    /// Whether the path of each game can be launched, by tab then by game.
+    health: Vec<Vec<PathStatus>>,
 
     block: Block<'static>,
     style_selected: Style,
     style_unselect: Style,
+    style_missing: Style,
+    style_not_executable: Style,
 }
 
 struct ListState {
@@ -34,6 +39,11 @@ impl TerminalPainter for ContentPainter {
 
         let dest_tab = &self.tabs[self.current_tab];
 
+        if let Some(ref picker) = self.variant_picker {
+            self.draw_variant_picker(f, picker, area);
+            return
+        }
+
         self.block
             .title(&dest_tab.name)
             .render(f, area);
@@ -54,12 +64,27 @@ impl ContentPainter {
             block: Block::default().borders(Borders::ALL),
             style_selected: Style::default().fg(Color::LightGreen).modifier(Modifier::Bold),
             style_unselect: Style::default().fg(Color::Gray),
+            style_missing: Style::default().fg(Color::Red),
+            style_not_executable: Style::default().fg(Color::Yellow),
             state: ListState { index: None, count: config.tabs[0].items.len() },
             current_tab: 0,
             tabs: config.tabs,
+            variant_picker: None,
+            health: vec![],
         }
     }
 
+    pub fn set_health(&mut self, health: Vec<Vec<PathStatus>>) {
+        self.health = health;
+    }
+
+    pub fn current_health(&self) -> PathStatus {
+
+        self.state.index
+            .and_then(|game_index| self.health.get(self.current_tab)?.get(game_index).cloned())
+            .unwrap_or(PathStatus::Ready)
+    }
+
     pub fn set_tab(&mut self, index: usize) {
         self.current_tab = index;
 
@@ -71,8 +96,8 @@ impl ContentPainter {
 
         match ops {
             | ConfigOp::AppendGame { tab_index, config } => {
-                self.tabs[*tab_index].items.push(config.clone());
-                self.set_tab(self.tabs.len() - 1)
+                self.tabs[*tab_index].items.push(config.as_ref().clone());
+                self.set_tab(*tab_index)
             },
             | ConfigOp::RemoveGame { tab_index, item_index } => {
                 self.tabs[*tab_index].items.remove(*item_index);
@@ -82,6 +107,16 @@ impl ContentPainter {
                 self.tabs.push(config.clone());
                 self.set_tab(self.tabs.len() - 1);
             },
+            | ConfigOp::SetVariant { tab_index, item_index, variant } => {
+                self.tabs[*tab_index].items[*item_index].variant = variant.clone();
+            },
+            | ConfigOp::SetPath { tab_index, item_index, path } => {
+                self.tabs[*tab_index].items[*item_index].path = path.clone();
+            },
+            | ConfigOp::SortGames { tab_index } => {
+                titles::sort_items(&mut self.tabs[*tab_index].items);
+                self.set_tab(*tab_index);
+            },
             | ConfigOp::RemoveTab { tab_index } => {
                 self.tabs.remove(*tab_index);
                 // TODO: Handle situation if all the tabs were removed.
@@ -100,31 +135,140 @@ impl ContentPainter {
 
     pub fn draw_game_list(&self, f: &mut crate::DstFrame, tab: &TabConfig, area: Rect) {
 
-        let games: Vec<&String> = tab.items.iter()
-            .map(|item| &item.name).collect();
+        let symbol = crate::config::manifest::HIGHLIGHT_SYMBOL;
+        // the highlight symbol is two columns wide.
+        let blank = "  ";
+        let health = self.health.get(self.current_tab);
+
+        let games = tab.items.iter().enumerate().map(|(index, item)| {
+            let name = match item.variant {
+                | Some(ref variant) => format!("{} [{}]", item.name, variant),
+                | None => item.name.clone(),
+            };
+
+            let status = health.and_then(|health| health.get(index)).cloned().unwrap_or(PathStatus::Ready);
+            let (icon, style) = match status {
+                | PathStatus::Ready => (" ", self.style_unselect),
+                | PathStatus::Missing => ("✗", self.style_missing),
+                | PathStatus::NotExecutable => ("!", self.style_not_executable),
+            };
+
+            let line = match titles::identify(item) {
+                | Some(title) => format!("{} {:<44} {}", icon, name, title.subtitle()),
+                | None => format!("{} {}", icon, name),
+            };
+
+            // the list of tui can not style its entries one by one, so it is drawn by hand.
+            if Some(index) == self.state.index {
+                let style = if status == PathStatus::Ready { self.style_selected } else { style.modifier(Modifier::Bold) };
+                Text::styled(format!("{} {}", symbol, line), style)
+            } else {
+                Text::styled(format!("{} {}", blank, line), style)
+            }
+        });
+
+        // keep the selected game in sight.
+        let offset = match self.state.index {
+            | Some(index) if index >= area.height as usize => index + 1 - area.height as usize,
+            | _ => 0,
+        };
+
+        List::new(games.skip(offset))
+            .render(f, area);
+    }
+
+    fn draw_variant_picker(&self, f: &mut crate::DstFrame, picker: &ListState, area: Rect) {
+
+        let current_program = match self.current_program() {
+            | Some(program) => program,
+            | None => {
+                self.block.title("Variants").render(f, area);
+                Paragraph::new([Text::raw("No game is selected.")].iter())
+                    .render(f, area.inner(1));
+                return
+            },
+        };
+
+        let title = format!("Variants of {}", current_program.name);
+        self.block.title(&title).render(f, area);
+
+        let variants: Vec<&str> = Some("Default").into_iter()
+            .chain(current_program.variants.iter().map(|variant| variant.name.as_str()))
+            .collect();
 
         SelectableList::default()
-            .items(&games)
-            .select(self.state.index)
+            .items(&variants)
+            .select(picker.index)
             .style(self.style_unselect)
             .highlight_style(self.style_selected)
             .highlight_symbol(crate::config::manifest::HIGHLIGHT_SYMBOL)
-            .render(f, area);
+            .render(f, area.inner(1));
     }
 
-    /// Launch current selected game.
-    pub fn launch(&self) -> Option<Result<ExitStatus, Error>> {
+    /// Show the variants of the selected game in place of the game list.
+    pub fn open_variant_picker(&mut self) {
 
-        if let Some(game_index) = self.state.index {
-            let game_path = &self.tabs[self.current_tab].items[game_index].path;
+        let picker = match self.current_program() {
+            | Some(program) => {
+                // the entry 0 is the game without any variant.
+                let index = program.variant.as_ref()
+                    .and_then(|name| program.variants.iter().position(|variant| &variant.name == name))
+                    .map_or(0, |index| index + 1);
+                ListState { index: Some(index), count: program.variants.len() + 1 }
+            },
+            | None => ListState { index: None, count: 0 },
+        };
 
-            let command = Command::new(&game_path).status();
-            Some(command)
-        } else {
-            None
+        self.variant_picker = Some(picker);
+    }
+
+    pub fn close_variant_picker(&mut self) {
+        self.variant_picker = None;
+    }
+
+    pub fn is_picking_variant(&self) -> bool {
+        self.variant_picker.is_some()
+    }
+
+    pub fn select_variant(&mut self, is_next: bool) {
+
+        if let Some(ref mut picker) = self.variant_picker {
+            if picker.count > 0 {
+                if is_next { picker.next() } else { picker.previous() }
+            }
         }
     }
 
+    /// Close the variant picker, returning the operation remembering the picked variant.
+    pub fn pick_variant(&mut self) -> ConfigOp {
+
+        let picked = self.variant_picker.take().and_then(|picker| picker.index);
+
+        match (self.state.index, picked) {
+            | (Some(item_index), Some(picked)) => {
+                let variant = match picked {
+                    | 0 => None,
+                    | _ => Some(self.tabs[self.current_tab].items[item_index].variants[picked - 1].name.clone()),
+                };
+
+                ConfigOp::SetVariant { tab_index: self.current_tab, item_index, variant }
+            },
+            | _ => ConfigOp::None,
+        }
+    }
+
+    pub fn tabs(&self) -> &[TabConfig] {
+        &self.tabs
+    }
+
+    pub fn current_tab(&self) -> &TabConfig {
+        &self.tabs[self.current_tab]
+    }
+
+    pub fn current_index(&self) -> Option<usize> {
+        self.state.index
+    }
+
     pub fn current_program(&self) -> Option<&ItemConfig> {
 
         self.state.index.and_then(|game_index| {
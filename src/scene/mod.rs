@@ -2,20 +2,45 @@
 mod navtab;
 mod content;
 mod ops;
+mod patches;
+mod review;
 
 use termion::event::Key;
 use tui::layout::{ Constraint, Direction, Layout, Rect };
 
+use std::collections::HashSet;
+use std::env;
+use std::path::{ Path, PathBuf };
+use std::time::{ Duration, Instant };
+
 use crate::scene::navtab::NavTabPainter;
 use crate::scene::content::ContentPainter;
 use crate::scene::ops::OperationPainter;
-use crate::config::tab::TabsConfig;
+use crate::scene::patches::PatchEditorPainter;
+use crate::scene::review::ScanReviewPainter;
+use crate::config::tab::{ TabsConfig, ItemConfig };
 use crate::config::setting::SettingConfig;
 use crate::config::manifest::EXIT_KEY;
 use crate::config::ConfigOp;
+use crate::launch::{ Launcher, LaunchOutcome, LaunchReport };
+use crate::launch::prefix::{ self, PrefixAction };
+use crate::launch::process::ProcessSignal;
+use crate::launch::winpath::{ self, PathMap };
+use crate::library::health::{ self, HealthCheck, PathStatus };
+use crate::library::install::{ self, Installation, InstallEvent };
+use crate::library::scan::{ self, Discovery, LibraryScan };
+use crate::library::lutris;
+use crate::library::steam;
+use crate::library::wineprefix;
+use crate::library::shortcuts::{ self, Shortcut };
+use crate::library::titles::{ self, TouhouTitle };
+use crate::library::versions::{ VersionCheck, VersionLookup };
 use crate::utils::{ THLEvents, THLEvent };
 
 
+/// How often the paths of the games are checked again, to notice drives being mounted or unmounted.
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
 pub trait TerminalPainter {
 
     fn draw(&mut self, f: &mut crate::DstFrame, area: Rect);
@@ -28,26 +53,56 @@ pub struct THLScene {
     navtab  : NavTabPainter,
     content : ContentPainter,
     ops     : OperationPainter,
+    /// The thcrap patch stack editor, drawn in place of the game list while it is open.
+    patches : Option<PatchEditorPainter>,
+    /// The archive being extracted into the library, reported on each poll.
+    install : Option<Installation>,
+    /// The games found by a library scan, drawn in place of the game list while they are reviewed.
+    scan    : Option<ScanReviewPainter>,
+
+    launcher: Launcher,
+    /// The prefix action waiting for the user to confirm it.
+    prefix_action: Option<PrefixAction>,
+    /// The check of the game paths in progress, if any.
+    health_check: Option<HealthCheck>,
+    /// When the paths of the games were last checked.
+    last_health_check: Instant,
+    /// The library scan in progress, its games are reviewed once it is done.
+    library_scan: Option<LibraryScan>,
+    /// The details of the selected game shown so far, and the lookup of its version.
+    details: Option<(Vec<String>, VersionLookup)>,
 }
 
 impl THLScene {
 
-    pub fn new(tabs: TabsConfig) -> THLScene {
+    pub fn new(tabs: TabsConfig, setting: SettingConfig) -> THLScene {
 
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Length(3),
                 Constraint::Min(5),
-                Constraint::Length(8),
+                Constraint::Length(15),
             ].as_ref());
 
-        THLScene {
+        let mut scene = THLScene {
             layout : chunks,
             navtab : NavTabPainter::new(&tabs),
             content: ContentPainter::new(tabs),
-            ops    : OperationPainter::new(),
-        }
+            ops    : OperationPainter::new(PathMap::from_setting(&setting)),
+            patches: None,
+            install: None,
+            scan   : None,
+            launcher: Launcher::new(setting),
+            prefix_action: None,
+            health_check: None,
+            last_health_check: Instant::now(),
+            library_scan: None,
+            details: None,
+        };
+
+        scene.refresh_health();
+        scene
     }
 
     pub fn react(&mut self, reaction: SceneReaction) -> ConfigOp {
@@ -55,30 +110,399 @@ impl THLScene {
         match reaction {
             | SceneReaction::LaunchGame => {
 
+                if let Some(current_program) = self.content.current_program().cloned() {
+                    self.launch(&current_program);
+                }
+            },
+            | SceneReaction::ShowActions => {
+
+                let names = self.content.current_program().map(|program| {
+                    program.actions.iter().map(|action| action.name.clone()).collect()
+                });
+
+                self.ops.switch_mode(THLOperation::ChoosingAction, None);
+                match names {
+                    | Some(names) => self.ops.set_actions(names),
+                    | None => self.ops.set_notice("No game is selected."),
+                }
+            },
+            | SceneReaction::RunAction => {
+
+                let selected = self.ops.selected_action();
+                self.ops.cancel_op();
+
+                // the first entry of the menu is the game itself.
+                let item = match (self.content.current_program(), selected) {
+                    | (Some(program), Some(0)) => Some(program.clone()),
+                    | (Some(program), Some(index)) => program.action_item(index - 1),
+                    | _ => None,
+                };
+
+                if let Some(item) = item {
+                    self.launch(&item);
+                }
+            },
+            | SceneReaction::KillGame => {
+
                 if let Some(current_program) = self.content.current_program() {
+                    if self.launcher.kill(self.content.current_tab(), current_program) {
+                        self.ops.set_notice(&format!("Killed {}.", current_program.name));
+                    }
+                }
+            },
+            | SceneReaction::Poll => {
+
+                if let Some(health) = self.health_check.as_ref().and_then(HealthCheck::poll) {
+                    self.content.set_health(health);
+                    self.health_check = None;
+                }
+                // a check stuck on an unresponsive mount is waited for, rather than piling up new ones.
+                if self.health_check.is_none() && self.last_health_check.elapsed() >= HEALTH_CHECK_INTERVAL {
+                    self.refresh_health();
+                }
+
+                if let Some((discoveries, steam_error)) = self.library_scan.as_ref().and_then(LibraryScan::poll) {
+                    self.library_scan = None;
+
+                    let notice = if let Some(e) = steam_error {
+                        e
+                    } else if discoveries.is_empty() {
+                        String::from("No game that is not in the library yet was found.")
+                    } else {
+                        format!("Found {} new games.", discoveries.len())
+                    };
+                    // the review may have been closed while the scan ran.
+                    if self.scan.is_some() {
+                        self.scan = Some(ScanReviewPainter::new("Library scan", discoveries, notice));
+                    }
+                }
+
+                if let Some(check) = self.details.as_ref().and_then(|(_, lookup)| lookup.poll()) {
+                    if let Some((mut lines, _)) = self.details.take() {
+                        lines.extend(describe_version(check));
+                        self.ops.switch_mode(THLOperation::ViewingDetails, Some(lines.join("\n")));
+                    }
+                }
+
+                for report in self.launcher.poll() {
+                    self.show_report(report);
+                }
+                self.ops.set_processes(self.launcher.processes());
+
+                if let Some(ops) = self.poll_install() {
+                    self.update_config(&ops);
+                    return ops
+                }
+            },
+            | SceneReaction::InstallArchive => self.ops.switch_mode(THLOperation::InstallingArchive, None),
+            | SceneReaction::StartInstall => {
+
+                let archive = self.ops.take_archive_path().unwrap_or_default();
+                if self.install.is_some() {
+                    self.ops.set_hint("Another archive is being installed, wait for it to finish.");
+                } else {
+                    let library_dir = install::library_dir(self.launcher.setting());
+                    match Installation::start(Path::new(&archive), &library_dir) {
+                        | Ok(installation) => {
+                            self.ops.set_notice(&format!("Installing {}...", installation.archive_name));
+                            self.install = Some(installation);
+                        },
+                        | Err(e) => self.ops.set_hint(&format!("Installation failed. {}", e)),
+                    }
+                }
+            },
+            | SceneReaction::ShowProcesses => {
+                self.ops.switch_mode(THLOperation::Running, None);
+                self.ops.set_processes(self.launcher.processes());
+            },
+            | SceneReaction::NextEntry => {
+                if let Some(ref mut editor) = self.patches {
+                    editor.select(true);
+                } else if let Some(ref mut review) = self.scan {
+                    review.select(true);
+                } else if self.content.is_picking_variant() {
+                    self.content.select_variant(true);
+                } else {
+                    self.ops.select_entry(true);
+                }
+            },
+            | SceneReaction::PreviousEntry => {
+                if let Some(ref mut editor) = self.patches {
+                    editor.select(false);
+                } else if let Some(ref mut review) = self.scan {
+                    review.select(false);
+                } else if self.content.is_picking_variant() {
+                    self.content.select_variant(false);
+                } else {
+                    self.ops.select_entry(false);
+                }
+            },
+            | SceneReaction::ShowVariants => {
+                self.content.open_variant_picker();
+                self.ops.switch_mode(THLOperation::ChoosingVariant, None);
+            },
+            | SceneReaction::ChooseVariant => {
 
-                    self.ops.switch_mode(THLOperation::Running, Some(current_program.name.clone()));
-                    let running_result = self.content.launch();
-                    self.ops.switch_mode(THLOperation::Common, None);
+                let ops = self.content.pick_variant();
+                self.update_config(&ops);
+                self.ops.cancel_op();
 
-                    running_result.and_then(|running_status| {
-                        use std::error::Error;
+                if let Some(current_program) = self.content.current_program().cloned() {
+                    self.launch(&current_program);
+                }
+                return ops
+            },
+            | SceneReaction::EditPatches => {
 
-                        match running_status {
-                            | Ok(status) => {
-                                if status.success() == false {
-                                    Some(format!("error code: {:?}", status.code()))
-                                } else {
-                                    None
-                                }
+                let thcrap = self.content.current_program()
+                    .map(|program| (program.name.clone(), program.resolve_variant().thcrap));
+
+                let message = match thcrap {
+                    | Some((_, Some(thcrap))) => {
+                        match PatchEditorPainter::open(&thcrap) {
+                            | Ok(editor) => {
+                                self.patches = Some(editor);
+                                format!("Editing the run configuration `{}` of {}.", thcrap.config, thcrap.dir)
                             },
-                            | Err(e) => Some(String::from(e.description())),
+                            | Err(e) => e,
                         }
-                    }).and_then(|hint| {
-                        Some(self.ops.set_running_error_hint(&hint))
-                    });
+                    },
+                    | Some((name, None)) => format!("{} is not launched through thcrap, set its `thcrap` table first.", name),
+                    | None => String::from("No game is selected."),
+                };
+
+                self.ops.switch_mode(THLOperation::EditingPatches, Some(message));
+            },
+            | SceneReaction::TogglePatch => {
+                if let Some(ref mut editor) = self.patches {
+                    editor.toggle();
+                }
+            },
+            | SceneReaction::MovePatchUp => {
+                if let Some(ref mut editor) = self.patches {
+                    editor.move_selected(true);
+                }
+            },
+            | SceneReaction::MovePatchDown => {
+                if let Some(ref mut editor) = self.patches {
+                    editor.move_selected(false);
+                }
+            },
+            | SceneReaction::SavePatches => {
+                if let Some(ref mut editor) = self.patches {
+                    editor.save();
                 }
             },
+            | SceneReaction::ScanLibrary => {
+
+                let path_map = PathMap::from_setting(self.launcher.setting());
+                let roots: Vec<PathBuf> = self.launcher.setting().scan_roots.iter()
+                    .map(|root| PathBuf::from(path_map.to_unix(root)))
+                    .collect();
+                let steam_root = steam::find_root(self.launcher.setting().steam_root.as_deref());
+
+                let notice = if roots.is_empty() && steam_root.is_none() {
+                    String::from("Set `scan_roots` in the setting to the directories containing your games.")
+                } else {
+                    let imported = steam::imported_app_ids(self.content.tabs());
+                    let app_ids = self.launcher.setting().steam_app_ids.clone();
+                    self.library_scan = Some(LibraryScan::start(roots, steam_root, imported, app_ids, self.known_paths()));
+                    String::from("Scanning the library...")
+                };
+
+                self.scan = Some(ScanReviewPainter::new("Library scan", vec![], notice));
+                self.ops.switch_mode(THLOperation::ReviewingScan, None);
+            },
+            | SceneReaction::ImportLutris => {
+
+                let (discoveries, notice) = match lutris::find_dir(self.launcher.setting().lutris_dir.as_deref()) {
+                    | Some(dir) => match lutris::import(&dir) {
+                        | Ok((found, warnings)) => {
+                            let discoveries = scan::arrange(found, &self.known_paths());
+                            let mut notice = if discoveries.is_empty() {
+                                format!("No Touhou game that is not in the library yet is configured in {}.", dir.display())
+                            } else {
+                                format!("Found {} new games in {}.", discoveries.len(), dir.display())
+                            };
+                            if !warnings.is_empty() {
+                                notice.push_str(&format!(" Skipped {} unreadable files: {}", warnings.len(), warnings.join("; ")));
+                            }
+                            (discoveries, notice)
+                        },
+                        | Err(e) => (vec![], e),
+                    },
+                    | None => (vec![], String::from("No Lutris games directory was found, set `lutris_dir` in the setting.")),
+                };
+
+                self.scan = Some(ScanReviewPainter::new("Lutris import", discoveries, notice));
+                self.ops.switch_mode(THLOperation::ReviewingScan, None);
+            },
+            | SceneReaction::ScanPrefixes => {
+
+                let managed_dir = prefix::managed_dir(self.launcher.setting());
+                let (prefixes, mut warnings) = wineprefix::find_prefixes(&self.launcher.setting().wine_prefixes, &managed_dir);
+
+                let (discoveries, mut notice) = if prefixes.is_empty() {
+                    (vec![], String::from("No Wine prefix was found, set `wine_prefixes` in the setting."))
+                } else {
+                    let (found, problems) = wineprefix::discover(&prefixes, &managed_dir);
+                    warnings.extend(problems);
+
+                    let discoveries = scan::arrange(found, &self.known_paths());
+                    let notice = if discoveries.is_empty() {
+                        format!("No game that is not in the library yet is installed in {} prefixes.", prefixes.len())
+                    } else {
+                        format!("Found {} new games in {} prefixes.", discoveries.len(), prefixes.len())
+                    };
+                    (discoveries, notice)
+                };
+                if !warnings.is_empty() {
+                    notice.push_str(&format!(" Skipped {} prefixes: {}", warnings.len(), warnings.join("; ")));
+                }
+
+                self.scan = Some(ScanReviewPainter::new("Wine prefixes", discoveries, notice));
+                self.ops.switch_mode(THLOperation::ReviewingScan, None);
+            },
+            | SceneReaction::ToggleDiscovery => {
+                if let Some(ref mut review) = self.scan {
+                    review.toggle();
+                }
+            },
+            | SceneReaction::ApplyScan => {
+
+                self.ops.cancel_op();
+                self.library_scan = None;
+                if let Some(review) = self.scan.take() {
+                    let ops = scan::into_ops(review.accepted(), self.content.tabs());
+                    self.update_config(&ops);
+                    return ops
+                }
+            },
+            | SceneReaction::ExportShortcuts => {
+                let report = self.export_shortcuts().join("\n");
+                self.ops.switch_mode(THLOperation::ExportingShortcuts, Some(report));
+            },
+            | SceneReaction::ShowDetails => {
+
+                let mut lines = self.describe_current_program();
+                self.details = None;
+
+                if let Some((path, title)) = self.content.current_program().map(|program| self.locate_program(&program.resolve_variant())) {
+                    let lookup = VersionLookup::start(self.content.tabs().to_vec(), PathBuf::from(&path), title);
+                    self.details = Some((lines.clone(), lookup));
+                    lines.push(String::from("version: hashing the executable..."));
+                }
+
+                self.ops.switch_mode(THLOperation::ViewingDetails, Some(lines.join("\n")));
+            },
+            | SceneReaction::ShowRelink => {
+
+                self.ops.switch_mode(THLOperation::Relinking, None);
+
+                let (choices, message) = match self.content.current_program() {
+                    | Some(program) if self.content.current_health() == PathStatus::Missing => {
+                        let path_map = PathMap::from_setting(self.launcher.setting());
+                        let roots: Vec<PathBuf> = self.launcher.setting().scan_roots.iter()
+                            .map(|root| PathBuf::from(path_map.to_unix(root)))
+                            .collect();
+                        let path = path_map.to_unix(&program.path);
+                        let candidates = health::relink_candidates(&path, &roots).into_iter()
+                            .map(|candidate| candidate.to_string_lossy().into_owned())
+                            .collect();
+                        (candidates, format!("No file named like {} was found nearby or under scan_roots.", path))
+                    },
+                    | Some(program) => (vec![], format!("{} is not missing, there is nothing to relink.", program.name)),
+                    | None => (vec![], String::from("No game is selected.")),
+                };
+
+                self.ops.set_choices(choices, &message);
+            },
+            | SceneReaction::Relink => {
+
+                let path = self.ops.selected_choice().map(str::to_owned);
+                self.ops.cancel_op();
+
+                // a game listed with a Windows path keeps one, the manifest may be shared with other machines.
+                let path = match (path, self.content.current_program()) {
+                    | (Some(path), Some(program)) if winpath::is_windows_path(&program.path) => {
+                        Some(PathMap::from_setting(self.launcher.setting()).to_windows(&path))
+                    },
+                    | (path, _) => path,
+                };
+
+                if let (Some(path), Some(item_index)) = (path, self.content.current_index()) {
+                    let ops = ConfigOp::SetPath { tab_index: self.navtab.current_index(), item_index, path };
+                    self.update_config(&ops);
+                    return ops
+                }
+            },
+            | SceneReaction::SortGames => {
+                let ops = ConfigOp::SortGames { tab_index: self.navtab.current_index() };
+                self.update_config(&ops);
+                return ops
+            },
+            | SceneReaction::ShowPrefix => {
+                self.prefix_action = None;
+                self.show_prefix(None);
+            },
+            | SceneReaction::PickPrefixAction(action) => {
+                self.prefix_action = Some(action);
+                let question = match action {
+                    | PrefixAction::Reset  => "Press y to delete and recreate the prefix, everything installed in it is lost.",
+                    | PrefixAction::Delete => "Press y to delete the prefix, everything installed in it is lost.",
+                };
+                self.show_prefix(Some(String::from(question)));
+            },
+            | SceneReaction::ConfirmPrefixAction => {
+
+                let message = match (self.prefix_action.take(), self.content.current_program()) {
+                    | (Some(action), Some(current_program)) => {
+                        match self.launcher.manage_prefix(self.content.current_tab(), current_program, action) {
+                            | Ok(message) | Err(message) => Some(message),
+                        }
+                    },
+                    | _ => None,
+                };
+                self.show_prefix(message);
+            },
+            | SceneReaction::SignalProcess(signal) => {
+
+                if let Some(pid) = self.ops.selected_process() {
+                    match self.launcher.signal(pid, signal) {
+                        | Ok(()) => self.ops.set_notice(&format!("Sent {:?} to pid {}.", signal, pid)),
+                        | Err(e) => self.ops.set_notice(&format!("Failed to signal pid {}: {}", pid, e)),
+                    }
+                }
+            },
+            | SceneReaction::ShowCommand => {
+
+                let preview = match self.content.current_program() {
+                    | Some(current_program) => {
+                        self.launcher.compose(self.content.current_tab(), current_program)
+                            .preview()
+                            .join("\n")
+                    },
+                    | None => String::from("No game is selected."),
+                };
+
+                self.ops.switch_mode(THLOperation::Previewing, Some(preview));
+            },
+            | SceneReaction::ShowFailures => {
+
+                let failures = match self.content.current_program() {
+                    | Some(current_program) => {
+                        let failures = self.launcher.failures(self.content.current_tab(), current_program);
+                        if failures.is_empty() {
+                            format!("No failure of {} was recorded in this session.", current_program.name)
+                        } else {
+                            failures.join("\n")
+                        }
+                    },
+                    | None => String::from("No game is selected."),
+                };
+
+                self.ops.switch_mode(THLOperation::ViewingFailures, Some(failures));
+            },
             | SceneReaction::NextTab => {
                 self.navtab.state.next();
 
@@ -95,7 +519,15 @@ impl THLScene {
             },
             | SceneReaction::NextGame => self.content.next_tab(),
             | SceneReaction::PreviousGame => self.content.previous_tab(),
-            | SceneReaction::CancelOp => self.ops.cancel_op(),
+            | SceneReaction::CancelOp => {
+                self.content.close_variant_picker();
+                self.patches = None;
+                self.scan = None;
+                self.library_scan = None;
+                self.prefix_action = None;
+                self.details = None;
+                self.ops.cancel_op();
+            },
             | SceneReaction::ConfirmAction => {
                 let ops = self.ops.confirm_op();
                 self.update_config(&ops);
@@ -106,7 +538,12 @@ impl THLScene {
             | SceneReaction::AppendGame => self.ops.switch_mode(THLOperation::AppendingGame, None),
             | SceneReaction::RemoveGame => self.ops.switch_mode(THLOperation::RemovingGame, None),
             | SceneReaction::UserInput(key) => self.ops.input_word(key),
-            | SceneReaction::SwitchInputFocus => self.ops.swtich_input_focus(),
+            | SceneReaction::SwitchInputFocus => {
+                match self.patches {
+                    | Some(ref mut editor) => editor.switch_focus(),
+                    | None => self.ops.swtich_input_focus(),
+                }
+            },
         }
 
         ConfigOp::None
@@ -118,13 +555,220 @@ impl THLScene {
             .split(f.size());
 
         self.navtab.draw(f, chunks[0]);
-        self.content.draw(f, chunks[1]);
+        match (&mut self.patches, &mut self.scan) {
+            | (Some(editor), _) => editor.draw(f, chunks[1]),
+            | (None, Some(review)) => review.draw(f, chunks[1]),
+            | (None, None) => self.content.draw(f, chunks[1]),
+        }
         self.ops.draw(f, chunks[2]);
     }
 
+    /// Launch `item` from the current tab and show how it went.
+    fn launch(&mut self, item: &ItemConfig) {
+
+        let tab = self.content.current_tab().clone();
+
+        match self.launcher.launch(&tab, item) {
+            | LaunchOutcome::Started { pid, warnings } => {
+                if warnings.is_empty() {
+                    self.ops.set_notice(&format!("{} is running (pid {}).", item.name, pid));
+                } else {
+                    self.ops.set_running_error_hint(&warnings.join("; "));
+                }
+            },
+            | LaunchOutcome::AlreadyRunning { pid } => {
+                self.ops.set_notice(&format!("{} is already running (pid {}). Press Ctrl + k to kill it.", item.name, pid));
+            },
+            | LaunchOutcome::Finished(report) => self.show_report(report),
+        }
+    }
+
+    /// Show the progress of the archive being installed, returning the operation adding its game once it is done.
+    fn poll_install(&mut self) -> Option<ConfigOp> {
+
+        let events = self.install.as_ref()?.poll();
+        let archive_name = self.install.as_ref()?.archive_name.clone();
+
+        for event in events.into_iter() {
+            match event {
+                | InstallEvent::Progress { done, total: 0 } => {
+                    self.ops.set_notice(&format!("Installing {}: {} files extracted.", archive_name, done));
+                },
+                | InstallEvent::Progress { done, total } => {
+                    let done = done.min(total);
+                    self.ops.set_notice(&format!("Installing {}: {}/{} files extracted ({}%).", archive_name, done, total, done * 100 / total));
+                },
+                | InstallEvent::Finished(Err(e)) => {
+                    self.install = None;
+                    self.ops.set_hint(&format!("Installation of {} failed. {}", archive_name, e));
+                },
+                | InstallEvent::Finished(Ok(installed)) => {
+                    self.install = None;
+                    self.ops.set_notice(&format!("Installed {} into {}.", archive_name, installed.dir.display()));
+
+                    let path = installed.exe.to_string_lossy().into_owned();
+                    return Some(match installed.title {
+                        | Some(title) => {
+                            let item = ItemConfig { name: title.display_name(), path, ..ItemConfig::default() };
+                            scan::into_ops(vec![Discovery { group: title.group, title, item }], self.content.tabs())
+                        },
+                        | None => ConfigOp::AppendGame {
+                            tab_index: self.navtab.current_index(),
+                            config: Box::new(ItemConfig { name: archive_name, path, ..ItemConfig::default() }),
+                        },
+                    })
+                },
+            }
+        }
+
+        None
+    }
+
+    /// The local paths of the games of the library, left out of imports.
+    fn known_paths(&self) -> HashSet<PathBuf> {
+
+        let path_map = PathMap::from_setting(self.launcher.setting());
+        self.content.tabs().iter()
+            .flat_map(|tab| tab.items.iter())
+            .map(|item| PathBuf::from(path_map.to_unix(&item.path)))
+            .collect()
+    }
+
+    /// Add every game of the library to the Steam shortcuts, describing the outcome.
+    fn export_shortcuts(&self) -> Vec<String> {
+
+        let steam_root = match steam::find_root(self.launcher.setting().steam_root.as_deref()) {
+            | Some(steam_root) => steam_root,
+            | None => return vec![String::from("No Steam installation was found, set `steam_root` in the setting.")],
+        };
+
+        let files = shortcuts::shortcut_files(&steam_root);
+        if files.is_empty() {
+            return vec![format!("No Steam account has logged in at {}.", steam_root.display())]
+        }
+
+        let (launcher, manifest_dir) = match (env::current_exe(), env::current_dir()) {
+            | (Ok(launcher), Ok(manifest_dir)) => (launcher, manifest_dir),
+            | (Err(e), _) | (_, Err(e)) => return vec![format!("Failed to locate the launcher: {}", e)],
+        };
+
+        let mut lines = vec![];
+        let exported: Vec<Shortcut> = self.content.tabs().iter()
+            .flat_map(|tab| tab.items.iter().map(move |item| (tab, item)))
+            .map(|(tab, item)| Shortcut::for_item(tab, item, &launcher, &manifest_dir))
+            .collect();
+
+        for file in files.iter() {
+            match shortcuts::export(file, &exported) {
+                | Ok(added) => lines.insert(0, format!("Added {} of {} games to {}.", added, exported.len(), file.display())),
+                | Err(e) => lines.insert(0, e),
+            }
+        }
+
+        lines
+    }
+
+    /// Describe the selected game: its title and executable. Its version is looked up in the background.
+    fn describe_current_program(&self) -> Vec<String> {
+
+        let current_program = match self.content.current_program() {
+            | Some(program) => program.resolve_variant(),
+            | None => return vec![String::from("No game is selected.")],
+        };
+
+        let (path, title) = self.locate_program(&current_program);
+        let mut lines = match title {
+            | Some(title) => vec![
+                format!("title  : {} {} / {}", title.id, title.english, title.japanese),
+                format!("about  : {}", title.subtitle()),
+            ],
+            | None => vec![format!("title  : {} is not in the title database", current_program.name)],
+        };
+        lines.push(format!("path   : {}", path));
+
+        lines
+    }
+
+    /// The local path of the executable of `program` and its game.
+    fn locate_program(&self, program: &ItemConfig) -> (String, Option<&'static TouhouTitle>) {
+
+        let path = PathMap::from_setting(self.launcher.setting()).to_unix(&program.path);
+        (path, titles::identify(program))
+    }
+
+    /// Show the managed Wine prefix of the selected game in the instruction panel, followed by `message`.
+    fn show_prefix(&mut self, message: Option<String>) {
+
+        let mut lines = match self.content.current_program() {
+            | Some(current_program) => match self.launcher.prefix(self.content.current_tab(), current_program) {
+                | Some(Ok(prefix)) => prefix.describe(),
+                | Some(Err(e)) => vec![e],
+                | None => vec![format!("{} has no managed wine prefix, set its `wine_prefix` first.", current_program.name)],
+            },
+            | None => vec![String::from("No game is selected.")],
+        };
+        lines.extend(message);
+
+        self.ops.switch_mode(THLOperation::ManagingPrefix, Some(lines.join("\n")));
+    }
+
+    fn show_report(&mut self, report: LaunchReport) {
+
+        let hints: Vec<String> = report.failure.into_iter()
+            .map(|failure| failure.to_string())
+            .chain(report.warnings)
+            .collect();
+
+        if hints.is_empty() {
+            self.ops.set_notice(&format!("{} has exited.", report.name));
+        } else {
+            self.ops.set_running_error_hint(&format!("{}: {}", report.name, hints.join("; ")));
+        }
+    }
+
     fn update_config(&mut self, ops: &ConfigOp) {
+
+        if let ConfigOp::Batch(ops) = ops {
+            for op in ops.iter() {
+                self.update_config(op);
+            }
+
+            // show the tab selected in the navigation, whichever tab the games went to.
+            let current_tab = self.navtab.current_index();
+            self.content.set_tab(current_tab);
+            self.ops.set_tab(current_tab);
+            return
+        }
+
         self.navtab.update_tabs(ops);
         self.content.update_tab(ops);
+        self.refresh_health();
+    }
+
+    /// Check the path of every game in the background, so the missing ones stand out in the list.
+    /// A check still running for the previous games is abandoned.
+    fn refresh_health(&mut self) {
+
+        let tabs = self.content.tabs().to_vec();
+        self.health_check = Some(HealthCheck::start(self.launcher.setting().clone(), tabs));
+        self.last_health_check = Instant::now();
+    }
+}
+
+fn describe_version(check: Result<VersionCheck, String>) -> Vec<String> {
+
+    match check {
+        | Ok(check) => {
+            let version = match check.version {
+                | Some(ref version) => format!("{} {} ({})", version.id, version.version, version.build),
+                | None => String::from("unknown"),
+            };
+
+            let mut lines = vec![format!("version: {}", version), format!("sha256 : {}", check.sha256)];
+            lines.extend(check.warnings.iter().map(|warning| format!("warning: {}", warning)));
+            lines
+        },
+        | Err(e) => vec![format!("version: {}", e)],
     }
 }
 
@@ -138,11 +782,19 @@ pub enum SceneAction {
 #[derive(Debug)]
 pub enum SceneReaction {
     NextTab,    PreviousTab,
-    NextGame,   PreviousGame, LaunchGame,
+    NextGame,   PreviousGame, LaunchGame, KillGame, ShowCommand, ShowFailures,
     AppendTab,  RemoveTab,
     AppendGame, RemoveGame, SwitchInputFocus,
     CancelOp,   ConfirmAction,
     UserInput(Key),
+    ShowActions, RunAction, ShowVariants, ChooseVariant,
+    ShowProcesses, NextEntry, PreviousEntry,
+    EditPatches, TogglePatch, MovePatchUp, MovePatchDown, SavePatches,
+    ShowPrefix, PickPrefixAction(PrefixAction), ConfirmPrefixAction,
+    ScanLibrary, ImportLutris, ScanPrefixes, ToggleDiscovery, InstallArchive, StartInstall, ApplyScan, SortGames, ShowDetails, ExportShortcuts,
+    ShowRelink, Relink,
+    SignalProcess(ProcessSignal),
+    Poll,
 }
 
 pub struct EventNerve {
@@ -156,10 +808,21 @@ pub struct EventNerve {
 pub enum THLOperation {
     Common,
     Running,
+    Previewing,
+    ViewingFailures,
+    ViewingDetails,
+    ExportingShortcuts,
+    ChoosingAction,
+    ChoosingVariant,
+    EditingPatches,
+    ManagingPrefix,
+    ReviewingScan,
+    Relinking,
     AppendingGame,
     RemovingGame,
     AppendingTab,
     RemovingTab,
+    InstallingArchive,
 }
 
 impl EventNerve {
@@ -188,6 +851,64 @@ impl EventNerve {
                         | Key::Char('\n') => {
                             return Ok(SceneAction::React(SceneReaction::LaunchGame))
                         },
+                        | Key::Ctrl('p') => {
+                            self.op = THLOperation::Previewing;
+                            return Ok(SceneAction::React(SceneReaction::ShowCommand))
+                        },
+                        | Key::Ctrl('k') => return Ok(SceneAction::React(SceneReaction::KillGame)),
+                        | Key::Ctrl('a') => {
+                            self.op = THLOperation::ChoosingAction;
+                            return Ok(SceneAction::React(SceneReaction::ShowActions))
+                        },
+                        | Key::Ctrl('v') => {
+                            self.op = THLOperation::ChoosingVariant;
+                            return Ok(SceneAction::React(SceneReaction::ShowVariants))
+                        },
+                        | Key::Ctrl('e') => {
+                            self.op = THLOperation::EditingPatches;
+                            return Ok(SceneAction::React(SceneReaction::EditPatches))
+                        },
+                        | Key::Ctrl('w') => {
+                            self.op = THLOperation::ManagingPrefix;
+                            return Ok(SceneAction::React(SceneReaction::ShowPrefix))
+                        },
+                        | Key::Ctrl('l') => {
+                            self.op = THLOperation::ReviewingScan;
+                            return Ok(SceneAction::React(SceneReaction::ScanLibrary))
+                        },
+                        | Key::Ctrl('u') => {
+                            self.op = THLOperation::ReviewingScan;
+                            return Ok(SceneAction::React(SceneReaction::ImportLutris))
+                        },
+                        | Key::Ctrl('y') => {
+                            self.op = THLOperation::ReviewingScan;
+                            return Ok(SceneAction::React(SceneReaction::ScanPrefixes))
+                        },
+                        | Key::Ctrl('z') => {
+                            self.op = THLOperation::InstallingArchive;
+                            return Ok(SceneAction::React(SceneReaction::InstallArchive))
+                        },
+                        | Key::Ctrl('g') => {
+                            self.op = THLOperation::ViewingDetails;
+                            return Ok(SceneAction::React(SceneReaction::ShowDetails))
+                        },
+                        | Key::Ctrl('b') => {
+                            self.op = THLOperation::Relinking;
+                            return Ok(SceneAction::React(SceneReaction::ShowRelink))
+                        },
+                        | Key::Ctrl('x') => {
+                            self.op = THLOperation::ExportingShortcuts;
+                            return Ok(SceneAction::React(SceneReaction::ExportShortcuts))
+                        },
+                        | Key::Ctrl('s') => return Ok(SceneAction::React(SceneReaction::SortGames)),
+                        | Key::Ctrl('o') => {
+                            self.op = THLOperation::Running;
+                            return Ok(SceneAction::React(SceneReaction::ShowProcesses))
+                        },
+                        | Key::Ctrl('f') => {
+                            self.op = THLOperation::ViewingFailures;
+                            return Ok(SceneAction::React(SceneReaction::ShowFailures))
+                        },
                         | Key::Ctrl('n') => {
                             self.op = THLOperation::AppendingGame;
                             return Ok(SceneAction::React(SceneReaction::AppendGame))
@@ -225,6 +946,22 @@ impl EventNerve {
                         | _ => {},
                     }
                 },
+                | THLOperation::InstallingArchive => {
+                    match key {
+                        | Key::Esc => {
+                            self.op = THLOperation::Common;
+                            return Ok(SceneAction::React(SceneReaction::CancelOp))
+                        },
+                        | Key::Char('\n') => {
+                            self.op = THLOperation::Common;
+                            return Ok(SceneAction::React(SceneReaction::StartInstall))
+                        },
+                        | Key::Char(_)
+                        | Key::Delete
+                        | Key::Backspace => return Ok(SceneAction::React(SceneReaction::UserInput(key))),
+                        | _ => {},
+                    }
+                },
                 | THLOperation::AppendingTab
                 | THLOperation::RemovingGame
                 | THLOperation::RemovingTab => {
@@ -243,10 +980,126 @@ impl EventNerve {
                         | _ => {},
                     }
                 },
+                | THLOperation::Previewing
+                | THLOperation::ViewingFailures
+                | THLOperation::ViewingDetails
+                | THLOperation::ExportingShortcuts => {
+                    match key {
+                        | Key::Esc
+                        | Key::Char('\n') => {
+                            self.op = THLOperation::Common;
+                            return Ok(SceneAction::React(SceneReaction::CancelOp))
+                        },
+                        | _ => {},
+                    }
+                },
+                | THLOperation::ChoosingAction => {
+                    match key {
+                        | Key::Esc => {
+                            self.op = THLOperation::Common;
+                            return Ok(SceneAction::React(SceneReaction::CancelOp))
+                        },
+                        | Key::Char('\n') => {
+                            self.op = THLOperation::Common;
+                            return Ok(SceneAction::React(SceneReaction::RunAction))
+                        },
+                        | Key::Down => return Ok(SceneAction::React(SceneReaction::NextEntry)),
+                        | Key::Up   => return Ok(SceneAction::React(SceneReaction::PreviousEntry)),
+                        | _ => {},
+                    }
+                },
+                | THLOperation::Relinking => {
+                    match key {
+                        | Key::Esc => {
+                            self.op = THLOperation::Common;
+                            return Ok(SceneAction::React(SceneReaction::CancelOp))
+                        },
+                        | Key::Char('\n') => {
+                            self.op = THLOperation::Common;
+                            return Ok(SceneAction::React(SceneReaction::Relink))
+                        },
+                        | Key::Down => return Ok(SceneAction::React(SceneReaction::NextEntry)),
+                        | Key::Up   => return Ok(SceneAction::React(SceneReaction::PreviousEntry)),
+                        | _ => {},
+                    }
+                },
+                | THLOperation::ChoosingVariant => {
+                    match key {
+                        | Key::Esc => {
+                            self.op = THLOperation::Common;
+                            return Ok(SceneAction::React(SceneReaction::CancelOp))
+                        },
+                        | Key::Char('\n') => {
+                            self.op = THLOperation::Common;
+                            return Ok(SceneAction::React(SceneReaction::ChooseVariant))
+                        },
+                        | Key::Down => return Ok(SceneAction::React(SceneReaction::NextEntry)),
+                        | Key::Up   => return Ok(SceneAction::React(SceneReaction::PreviousEntry)),
+                        | _ => {},
+                    }
+                },
+                | THLOperation::EditingPatches => {
+                    match key {
+                        | Key::Esc => {
+                            self.op = THLOperation::Common;
+                            return Ok(SceneAction::React(SceneReaction::CancelOp))
+                        },
+                        | Key::Char('\n') => return Ok(SceneAction::React(SceneReaction::TogglePatch)),
+                        | Key::Char('\t') => return Ok(SceneAction::React(SceneReaction::SwitchInputFocus)),
+                        | Key::Char('u') => return Ok(SceneAction::React(SceneReaction::MovePatchUp)),
+                        | Key::Char('d') => return Ok(SceneAction::React(SceneReaction::MovePatchDown)),
+                        | Key::Ctrl('s') => return Ok(SceneAction::React(SceneReaction::SavePatches)),
+                        | Key::Down => return Ok(SceneAction::React(SceneReaction::NextEntry)),
+                        | Key::Up   => return Ok(SceneAction::React(SceneReaction::PreviousEntry)),
+                        | _ => {},
+                    }
+                },
+                | THLOperation::ManagingPrefix => {
+                    match key {
+                        | Key::Esc => {
+                            self.op = THLOperation::Common;
+                            return Ok(SceneAction::React(SceneReaction::CancelOp))
+                        },
+                        | Key::Char('r') => return Ok(SceneAction::React(SceneReaction::PickPrefixAction(PrefixAction::Reset))),
+                        | Key::Char('x') => return Ok(SceneAction::React(SceneReaction::PickPrefixAction(PrefixAction::Delete))),
+                        | Key::Char('y') => return Ok(SceneAction::React(SceneReaction::ConfirmPrefixAction)),
+                        | _ => {},
+                    }
+                },
+                | THLOperation::ReviewingScan => {
+                    match key {
+                        | Key::Esc => {
+                            self.op = THLOperation::Common;
+                            return Ok(SceneAction::React(SceneReaction::CancelOp))
+                        },
+                        | Key::Char('\n') => {
+                            self.op = THLOperation::Common;
+                            return Ok(SceneAction::React(SceneReaction::ApplyScan))
+                        },
+                        | Key::Char(' ') => return Ok(SceneAction::React(SceneReaction::ToggleDiscovery)),
+                        | Key::Down => return Ok(SceneAction::React(SceneReaction::NextEntry)),
+                        | Key::Up   => return Ok(SceneAction::React(SceneReaction::PreviousEntry)),
+                        | _ => {},
+                    }
+                },
                 | THLOperation::Running => {
-                    unreachable!()
+                    match key {
+                        | Key::Esc => {
+                            self.op = THLOperation::Common;
+                            return Ok(SceneAction::React(SceneReaction::CancelOp))
+                        },
+                        | Key::Down => return Ok(SceneAction::React(SceneReaction::NextEntry)),
+                        | Key::Up   => return Ok(SceneAction::React(SceneReaction::PreviousEntry)),
+                        | Key::Char('t') => return Ok(SceneAction::React(SceneReaction::SignalProcess(ProcessSignal::Terminate))),
+                        | Key::Char('k') => return Ok(SceneAction::React(SceneReaction::SignalProcess(ProcessSignal::Kill))),
+                        | Key::Char('g') => return Ok(SceneAction::React(SceneReaction::SignalProcess(ProcessSignal::KillGroup))),
+                        | _ => {},
+                    }
                 },
             }
+        } else {
+            // reap the games running in the background.
+            return Ok(SceneAction::React(SceneReaction::Poll))
         }
 
         Ok(SceneAction::Rendering)
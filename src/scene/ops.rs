@@ -2,7 +2,7 @@
 use termion::event::Key;
 use tui::layout::{ Layout, Rect, Direction, Constraint };
 use tui::style::{ Style, Color };
-use tui::widgets::{ Block, Text, Paragraph, Borders, Widget };
+use tui::widgets::{ Block, Text, Paragraph, Borders, SelectableList, Widget };
 
 use std::path::Path;
 use std::str::FromStr;
@@ -11,6 +11,9 @@ use crate::scene::TerminalPainter;
 use crate::scene::THLOperation;
 use crate::config::ConfigOp;
 use crate::config::tab::{ TabConfig, ItemConfig };
+use crate::launch::process::ProcessInfo;
+use crate::launch::winpath::PathMap;
+use crate::library::titles;
 
 pub struct OperationPainter {
 
@@ -19,6 +22,7 @@ pub struct OperationPainter {
 
     current_tab: usize,
     instruction: InstructionType,
+    path_map: PathMap,
 }
 
 impl TerminalPainter for OperationPainter {
@@ -36,6 +40,19 @@ impl TerminalPainter for OperationPainter {
             },
             | InstructionType::Running(ref v) => {
                 v.draw_ops(f, chunks[0]);
+                v.draw_hints(f, chunks[1]);
+            },
+            | InstructionType::Actions(ref v) => {
+                v.draw_ops(f, chunks[0]);
+                v.draw_hints(f, chunks[1]);
+            },
+            | InstructionType::Choice(ref v) => {
+                v.draw_ops(f, chunks[0]);
+                v.draw_hints(f, chunks[1]);
+            },
+            | InstructionType::Preview(ref v) => {
+                v.draw_ops(f, chunks[0]);
+                v.draw_hints(f, chunks[1]);
             },
             | InstructionType::NewGame(ref v) => {
                 v.draw_ops(f, chunks[0]);
@@ -53,13 +70,17 @@ impl TerminalPainter for OperationPainter {
                 v.draw_ops(f, chunks[0]);
                 v.draw_hints(f, chunks[1]);
             },
+            | InstructionType::Install(ref v) => {
+                v.draw_ops(f, chunks[0]);
+                v.draw_hints(f, chunks[1]);
+            },
         }
     }
 }
 
 impl OperationPainter {
 
-    pub fn new() -> OperationPainter {
+    pub fn new(path_map: PathMap) -> OperationPainter {
 
         let block = Block::default()
             .title("Instruction")
@@ -73,6 +94,7 @@ impl OperationPainter {
             block, layout,
             current_tab: 0,
             instruction: InstructionType::Common(CommonInstruction::new()),
+            path_map,
         }
     }
 
@@ -87,7 +109,18 @@ impl OperationPainter {
             | THLOperation::AppendingTab  => self.instruction = InstructionType::NewTab(NewTabInstruction::new()),
             | THLOperation::RemovingGame  => self.instruction = InstructionType::RemoveGame(RemoveGameInstruction::new()),
             | THLOperation::RemovingTab   => self.instruction = InstructionType::RemoveTab(RemoveTabInstruction::new()),
-            | THLOperation::Running       => self.instruction = InstructionType::Running(RunningInstruction::new(mess.unwrap())),
+            | THLOperation::InstallingArchive => self.instruction = InstructionType::Install(InstallInstruction::new()),
+            | THLOperation::Running       => self.instruction = InstructionType::Running(RunningInstruction::new()),
+            | THLOperation::ChoosingVariant => self.instruction = InstructionType::Preview(PreviewInstruction::new(String::from("Use Up and Down arrow to select a variant of the game."), "Press Enter to remember it and launch the game.\nPress ESC to cancel.")),
+            | THLOperation::EditingPatches => self.instruction = InstructionType::Preview(PreviewInstruction::new(mess.unwrap_or_default(), "[Enter]Add or remove  [u/d]Move up or down  [Tab]Switch list  [Ctrl + s]Save\nPress ESC to close without saving.")),
+            | THLOperation::ManagingPrefix => self.instruction = InstructionType::Preview(PreviewInstruction::new(mess.unwrap_or_default(), "[r]Reset  [x]Delete\nPress ESC to close.")),
+            | THLOperation::ReviewingScan => self.instruction = InstructionType::Preview(PreviewInstruction::new(String::from("Use Up and Down arrow to select a game, Space to accept or reject it."), "Press Enter to add the accepted games to the library.\nPress ESC to cancel.")),
+            | THLOperation::Relinking => self.instruction = InstructionType::Choice(ChoiceInstruction::new("Press Enter to use the selected file for the game.\nPress ESC to cancel.")),
+            | THLOperation::ChoosingAction => self.instruction = InstructionType::Actions(ActionsInstruction::new()),
+            | THLOperation::Previewing    => self.instruction = InstructionType::Preview(PreviewInstruction::new(mess.unwrap_or_default(), "Nothing has been executed.\nPress Enter or ESC to close.")),
+            | THLOperation::ViewingDetails => self.instruction = InstructionType::Preview(PreviewInstruction::new(mess.unwrap_or_default(), "Add verified checksums to thl-versions.txt next to the manifest.\nPress Enter or ESC to close.")),
+            | THLOperation::ExportingShortcuts => self.instruction = InstructionType::Preview(PreviewInstruction::new(mess.unwrap_or_default(), "Close Steam before exporting, it rewrites the shortcuts when it exits.\nPress Enter or ESC to close.")),
+            | THLOperation::ViewingFailures => self.instruction = InstructionType::Preview(PreviewInstruction::new(mess.unwrap_or_default(), "Failures are also written to the session log.\nPress Enter or ESC to close.")),
         }
     }
 
@@ -98,6 +131,7 @@ impl OperationPainter {
             | InstructionType::NewTab(ref mut inst)     => inst.receive_input(key),
             | InstructionType::RemoveGame(ref mut inst) => inst.receive_input(key),
             | InstructionType::RemoveTab(ref mut inst)  => inst.receive_input(key),
+            | InstructionType::Install(ref mut inst)    => inst.receive_input(key),
             | _ => unreachable!(),
         }
     }
@@ -118,9 +152,112 @@ impl OperationPainter {
 
         if let InstructionType::Common(ref mut inst) = self.instruction {
             inst.hint = Some(String::from(format!("Some errors occur during the program running: {}", mess)));
+            inst.notice = None;
+        }
+    }
+
+    /// Show an error that is not about a running game, such as a failed installation.
+    pub fn set_hint(&mut self, mess: &str) {
+
+        if let InstructionType::Common(ref mut inst) = self.instruction {
+            inst.hint = Some(String::from(mess));
+            inst.notice = None;
+        }
+    }
+
+    /// Show a message that is not an error, such as a game being started.
+    pub fn set_notice(&mut self, mess: &str) {
+
+        match self.instruction {
+            | InstructionType::Common(ref mut inst) => {
+                inst.hint = None;
+                inst.notice = Some(String::from(mess));
+            },
+            | InstructionType::Running(ref mut inst) => {
+                inst.notice = Some(String::from(mess));
+            },
+            | _ => {},
+        }
+    }
+
+    /// Refresh the process list, if it is shown.
+    pub fn set_processes(&mut self, processes: Vec<ProcessInfo>) {
+
+        if let InstructionType::Running(ref mut inst) = self.instruction {
+            inst.set_processes(processes);
+        }
+    }
+
+    /// Move the selection of the process list or the actions menu.
+    pub fn select_entry(&mut self, is_next: bool) {
+
+        match self.instruction {
+            | InstructionType::Running(ref mut inst) => inst.select(is_next),
+            | InstructionType::Actions(ref mut inst) => {
+                inst.selected = cycle_selection(inst.selected, inst.actions.len() + 1, is_next);
+            },
+            | InstructionType::Choice(ref mut inst) => {
+                inst.selected = cycle_selection(inst.selected, inst.choices.len(), is_next);
+            },
+            | _ => {},
+        }
+    }
+
+    /// Fill the list of choices, or show `empty_message` in its place when there is none.
+    pub fn set_choices(&mut self, choices: Vec<String>, empty_message: &str) {
+
+        if let InstructionType::Choice(ref mut inst) = self.instruction {
+            inst.selected = if choices.is_empty() { None } else { Some(0) };
+            inst.choices = choices;
+            inst.empty_message = empty_message.to_owned();
+        }
+    }
+
+    pub fn selected_choice(&self) -> Option<&str> {
+
+        match self.instruction {
+            | InstructionType::Choice(ref inst) => inst.selected.and_then(|index| inst.choices.get(index)).map(String::as_str),
+            | _ => None,
+        }
+    }
+
+    /// Fill the actions menu with the action names of the selected game.
+    pub fn set_actions(&mut self, actions: Vec<String>) {
+
+        if let InstructionType::Actions(ref mut inst) = self.instruction {
+            inst.actions = actions;
+            inst.selected = Some(0);
+        }
+    }
+
+    /// The selected entry of the actions menu, where 0 is the game itself.
+    pub fn selected_action(&self) -> Option<usize> {
+
+        match self.instruction {
+            | InstructionType::Actions(ref inst) => inst.selected,
+            | _ => None,
         }
     }
 
+    pub fn selected_process(&self) -> Option<u32> {
+
+        match self.instruction {
+            | InstructionType::Running(ref inst) => inst.selected_pid(),
+            | _ => None,
+        }
+    }
+
+    /// Close the archive prompt, returning the path typed in it.
+    pub fn take_archive_path(&mut self) -> Option<String> {
+
+        let path = match self.instruction {
+            | InstructionType::Install(ref inst) => Some(inst.input_path.clone()),
+            | _ => None,
+        };
+        self.cancel_op();
+        path
+    }
+
     pub fn confirm_op(&mut self) -> ConfigOp {
 
         let (result, instruction) = match self.instruction {
@@ -128,12 +265,24 @@ impl OperationPainter {
 
                 let mut new_inst = CommonInstruction::new();
 
-                let path = Path::new(&inst.input_path);
+                // the path is kept as typed, so a manifest shared with Windows stays valid there.
+                let local_path = self.path_map.to_unix(&inst.input_path);
+                let path = Path::new(&local_path);
+
+                // a game known by the title database is named after it.
+                let name = if inst.input_name.is_empty() {
+                    path.file_name()
+                        .and_then(|file_name| titles::by_executable(&file_name.to_string_lossy()))
+                        .map(|title| title.display_name())
+                        .unwrap_or_default()
+                } else {
+                    inst.input_name.clone()
+                };
 
                 let mut is_success = true;
-                if inst.input_name.is_empty() {
+                if name.is_empty() {
                     is_success = false;
-                    new_inst.hint = Some(String::from("Operation failed. Name must not be empty."));
+                    new_inst.hint = Some(String::from("Operation failed. Name must not be empty for an unknown game."));
                 }
                 if inst.input_path.is_empty() {
                     is_success = false;
@@ -147,10 +296,11 @@ impl OperationPainter {
                 let result = if is_success {
                     ConfigOp::AppendGame {
                         tab_index: self.current_tab,
-                        config: ItemConfig {
-                            name: inst.input_name.clone(),
+                        config: Box::new(ItemConfig {
+                            name,
                             path: inst.input_path.clone(),
-                        }
+                            ..ItemConfig::default()
+                        }),
                     }
                 } else {
                     ConfigOp::None
@@ -170,6 +320,7 @@ impl OperationPainter {
                         config: TabConfig {
                             name: inst.input_name.clone(),
                             items: vec![],
+                            ..TabConfig::default()
                         }
                     }
                 };
@@ -216,9 +367,14 @@ impl OperationPainter {
             | InstructionType::Common(ref mut inst) => {
 
                 inst.hint = None;
+                inst.notice = None;
                 return ConfigOp::None
             },
-            | InstructionType::Running(_) => {
+            | InstructionType::Running(_)
+            | InstructionType::Actions(_)
+            | InstructionType::Choice(_)
+            | InstructionType::Preview(_)
+            | InstructionType::Install(_) => {
                 unreachable!()
             }
         };
@@ -228,6 +384,20 @@ impl OperationPainter {
     }
 }
 
+/// Move a list selection by one entry, wrapping around at both ends.
+pub fn cycle_selection(selected: Option<usize>, count: usize, is_next: bool) -> Option<usize> {
+
+    if count == 0 {
+        return None
+    }
+
+    Some(match selected {
+        | Some(index) if is_next => (index + 1) % count,
+        | Some(index) => (index + count - 1) % count,
+        | None => 0,
+    })
+}
+
 trait DrawableInstruction where Self: Sized {
 
     fn draw_ops(&self, f: &mut crate::DstFrame, area: Rect);
@@ -238,10 +408,14 @@ enum InstructionType {
 
     Common(CommonInstruction),
     Running(RunningInstruction),
+    Actions(ActionsInstruction),
+    Choice(ChoiceInstruction),
+    Preview(PreviewInstruction),
     NewGame(NewGameInstruction),
     NewTab(NewTabInstruction),
     RemoveGame(RemoveGameInstruction),
     RemoveTab(RemoveTabInstruction),
+    Install(InstallInstruction),
 }
 
 
@@ -249,7 +423,9 @@ enum InstructionType {
 struct CommonInstruction {
 
     hint: Option<String>,
+    notice: Option<String>,
     style_hint: Style,
+    style_notice: Style,
     ops_layout: Layout,
 }
 
@@ -262,14 +438,33 @@ impl DrawableInstruction for CommonInstruction {
             Text::raw("[Ctrl + t]Append a new tab.\n"),
             Text::raw("[Ctrl + d]Remove a game.      "),
             Text::raw("[Ctrl + r]Remove a tab.\n"),
+            Text::raw("[Ctrl + p]Show launch command."),
+            Text::raw("[Ctrl + f]Show recent failures.\n"),
+            Text::raw("[Ctrl + k]Kill the selected game."),
+            Text::raw("[Ctrl + o]Show running games.\n"),
+            Text::raw("[Ctrl + a]Show game actions.    "),
+            Text::raw("[Ctrl + v]Pick a game variant.\n"),
+            Text::raw("[Ctrl + e]Edit thcrap patches."),
+            Text::raw("[Ctrl + w]Manage the Wine prefix.\n"),
+            Text::raw("[Ctrl + l]Scan for games.       "),
+            Text::raw("[Ctrl + s]Sort the tab by release.\n"),
+            Text::raw("[Ctrl + g]Show game details.  "),
+            Text::raw("[Ctrl + b]Relink a missing game.\n"),
+            Text::raw("[Ctrl + x]Export to Steam.    "),
+            Text::raw("[Ctrl + u]Import from Lutris.\n"),
+            Text::raw("[Ctrl + y]Scan Wine prefixes. "),
+            Text::raw("[Ctrl + z]Install from an archive.\n"),
         ];
 
-        if let Some(ref hint) = self.hint {
+        let message = self.hint.as_ref().map(|hint| (hint, self.style_hint))
+            .or_else(|| self.notice.as_ref().map(|notice| (notice, self.style_notice)));
+
+        if let Some((message, style)) = message {
 
             let chunks = self.ops_layout.clone().split(area);
             Paragraph::new(texts.iter())
                 .render(f, chunks[0]);
-            Paragraph::new([Text::raw(hint)].iter()).style(self.style_hint)
+            Paragraph::new([Text::raw(message)].iter()).style(style)
                 .render(f, chunks[1]);
         } else {
             Paragraph::new(texts.iter())
@@ -294,10 +489,12 @@ impl CommonInstruction {
     fn new() -> CommonInstruction {
         CommonInstruction {
             hint: None,
+            notice: None,
             style_hint: Style::default().fg(Color::Red),
+            style_notice: Style::default().fg(Color::Green),
             ops_layout: Layout::default()
                 .direction(Direction::Vertical)
-                .constraints([Constraint::Length(2), Constraint::Length(1)].as_ref()),
+                .constraints([Constraint::Length(10), Constraint::Length(1)].as_ref()),
         }
     }
 }
@@ -457,6 +654,60 @@ impl NewTabInstruction {
 }
 // --------------------------------------------------------------------------------------
 
+// Instruction. -------------------------------------------------------------------------
+struct InstallInstruction {
+
+    input_path: String,
+    text_style: Style,
+}
+
+impl DrawableInstruction for InstallInstruction {
+
+    fn draw_ops(&self, f: &mut crate::DstFrame, area: Rect) {
+
+        let input_texts = [
+            Text::raw("Archive (zip, 7z or rar): "),
+            Text::raw(&self.input_path),
+        ];
+
+        Paragraph::new(input_texts.iter())
+            .style(self.text_style)
+            .render(f, area);
+    }
+
+    fn draw_hints(&self, f: &mut crate::DstFrame, area: Rect) {
+
+        let texts = [
+            Text::raw("Press Enter to extract it into the library, its progress shows here.\n"),
+            Text::raw("Press ESC to cancel."),
+        ];
+
+        Paragraph::new(texts.iter())
+            .render(f, area);
+    }
+}
+
+impl InstallInstruction {
+
+    fn new() -> InstallInstruction {
+
+        InstallInstruction {
+            input_path: String::new(),
+            text_style: Style::default().fg(Color::Yellow),
+        }
+    }
+
+    fn receive_input(&mut self, key: Key) {
+
+        match key {
+            | Key::Backspace => { self.input_path.pop(); }
+            | Key::Char(ch)  => self.input_path.push(ch),
+            | _ => {},
+        }
+    }
+}
+// --------------------------------------------------------------------------------------
+
 // Instruction. -------------------------------------------------------------------------
 struct RemoveGameInstruction {
 
@@ -560,35 +811,235 @@ impl RemoveTabInstruction {
 // --------------------------------------------------------------------------------------
 
 // Instruction. -------------------------------------------------------------------------
+/// The games running in the background, with actions to stop them.
 struct RunningInstruction {
 
-    program: String,
+    processes: Vec<ProcessInfo>,
+    selected: Option<usize>,
+    notice: Option<String>,
+
+    style_selected: Style,
+    style_unselect: Style,
 }
 
 impl DrawableInstruction for RunningInstruction {
 
     fn draw_ops(&self, f: &mut crate::DstFrame, area: Rect) {
 
-        let input_texts = [
-            Text::raw("Running: "),
-            Text::raw(&self.program),
-            Text::raw("..."),
-        ];
+        if self.processes.is_empty() {
+            Paragraph::new([Text::raw("No game is running.")].iter())
+                .render(f, area);
+            return
+        }
 
-        Paragraph::new(input_texts.iter())
+        let lines: Vec<String> = self.processes.iter()
+            .map(|process| process.describe()).collect();
+
+        SelectableList::default()
+            .items(&lines)
+            .select(self.selected)
+            .style(self.style_unselect)
+            .highlight_style(self.style_selected)
+            .highlight_symbol(crate::config::manifest::HIGHLIGHT_SYMBOL)
             .render(f, area);
     }
 
-    fn draw_hints(&self, _: &mut crate::DstFrame, _: Rect) {
-        // ignore this func...
+    fn draw_hints(&self, f: &mut crate::DstFrame, area: Rect) {
+
+        let first_line = match self.notice {
+            | Some(ref notice) => format!("{}\n", notice),
+            | None => String::from("[t]Terminate  [k]Kill  [g]Kill the whole process group.\n"),
+        };
+
+        let texts = [
+            Text::raw(first_line),
+            Text::raw("Use Up and Down arrow to select a game. Press ESC to close."),
+        ];
+
+        Paragraph::new(texts.iter())
+            .render(f, area);
     }
 }
 
 impl RunningInstruction {
 
-    fn new(program: String) -> RunningInstruction {
+    fn new() -> RunningInstruction {
+
+        RunningInstruction {
+            processes: vec![],
+            selected: None,
+            notice: None,
+            style_selected: Style::default().fg(Color::LightGreen),
+            style_unselect: Style::default().fg(Color::Gray),
+        }
+    }
+
+    fn set_processes(&mut self, processes: Vec<ProcessInfo>) {
+
+        // keep the same game selected while the others come and go.
+        let selected_pid = self.selected_pid();
+        self.processes = processes;
 
-        RunningInstruction { program }
+        let selected = selected_pid.and_then(|pid| {
+            self.processes.iter().position(|process| process.pid == pid)
+        });
+        self.selected = if self.processes.is_empty() { None } else { selected.or(Some(0)) };
+    }
+
+    fn select(&mut self, is_next: bool) {
+        self.selected = cycle_selection(self.selected, self.processes.len(), is_next);
+    }
+
+    fn selected_pid(&self) -> Option<u32> {
+        self.selected.and_then(|index| self.processes.get(index)).map(|process| process.pid)
+    }
+}
+// --------------------------------------------------------------------------------------
+
+// Instruction. -------------------------------------------------------------------------
+/// The actions menu of the selected game. The game itself is always the first entry.
+struct ActionsInstruction {
+
+    actions: Vec<String>,
+    selected: Option<usize>,
+
+    style_selected: Style,
+    style_unselect: Style,
+}
+
+impl DrawableInstruction for ActionsInstruction {
+
+    fn draw_ops(&self, f: &mut crate::DstFrame, area: Rect) {
+
+        let entries: Vec<&str> = Some("Play").into_iter()
+            .chain(self.actions.iter().map(|action| action.as_str()))
+            .collect();
+
+        SelectableList::default()
+            .items(&entries)
+            .select(self.selected)
+            .style(self.style_unselect)
+            .highlight_style(self.style_selected)
+            .highlight_symbol(crate::config::manifest::HIGHLIGHT_SYMBOL)
+            .render(f, area);
+    }
+
+    fn draw_hints(&self, f: &mut crate::DstFrame, area: Rect) {
+
+        let texts = [
+            Text::raw("Use Up and Down arrow to select an action. Press Enter to run it.\n"),
+            Text::raw("Press ESC to cancel."),
+        ];
+
+        Paragraph::new(texts.iter())
+            .render(f, area);
+    }
+}
+
+impl ActionsInstruction {
+
+    fn new() -> ActionsInstruction {
+
+        ActionsInstruction {
+            actions: vec![],
+            selected: None,
+            style_selected: Style::default().fg(Color::LightGreen),
+            style_unselect: Style::default().fg(Color::Gray),
+        }
+    }
+}
+// --------------------------------------------------------------------------------------
+
+// Instruction. -------------------------------------------------------------------------
+/// A list to pick one entry from, such as the files a missing game can be relinked to.
+struct ChoiceInstruction {
+
+    choices: Vec<String>,
+    selected: Option<usize>,
+    empty_message: String,
+    footer: &'static str,
+
+    style_selected: Style,
+    style_unselect: Style,
+}
+
+impl DrawableInstruction for ChoiceInstruction {
+
+    fn draw_ops(&self, f: &mut crate::DstFrame, area: Rect) {
+
+        if self.choices.is_empty() {
+            Paragraph::new([Text::raw(&self.empty_message)].iter())
+                .wrap(true)
+                .render(f, area);
+            return
+        }
+
+        SelectableList::default()
+            .items(&self.choices)
+            .select(self.selected)
+            .style(self.style_unselect)
+            .highlight_style(self.style_selected)
+            .highlight_symbol(crate::config::manifest::HIGHLIGHT_SYMBOL)
+            .render(f, area);
+    }
+
+    fn draw_hints(&self, f: &mut crate::DstFrame, area: Rect) {
+
+        Paragraph::new([Text::raw(self.footer)].iter())
+            .render(f, area);
+    }
+}
+
+impl ChoiceInstruction {
+
+    fn new(footer: &'static str) -> ChoiceInstruction {
+
+        ChoiceInstruction {
+            choices: vec![],
+            selected: None,
+            empty_message: String::new(),
+            footer,
+            style_selected: Style::default().fg(Color::LightGreen),
+            style_unselect: Style::default().fg(Color::Gray),
+        }
+    }
+}
+// --------------------------------------------------------------------------------------
+
+// Instruction. -------------------------------------------------------------------------
+/// Read-only text, such as the launch command preview or the failure history, above two lines of hints.
+struct PreviewInstruction {
+
+    content: String,
+    footer: &'static str,
+    text_style: Style,
+}
+
+impl DrawableInstruction for PreviewInstruction {
+
+    fn draw_ops(&self, f: &mut crate::DstFrame, area: Rect) {
+
+        Paragraph::new([Text::raw(&self.content)].iter())
+            .style(self.text_style)
+            .wrap(true)
+            .render(f, area);
+    }
+
+    fn draw_hints(&self, f: &mut crate::DstFrame, area: Rect) {
+
+        Paragraph::new([Text::raw(self.footer)].iter())
+            .render(f, area);
+    }
+}
+
+impl PreviewInstruction {
+
+    fn new(content: String, footer: &'static str) -> PreviewInstruction {
+
+        PreviewInstruction {
+            content, footer,
+            text_style: Style::default().fg(Color::Cyan),
+        }
     }
 }
 // --------------------------------------------------------------------------------------
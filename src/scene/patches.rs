@@ -0,0 +1,165 @@
+
+use tui::layout::{ Layout, Rect, Direction, Constraint };
+use tui::style::{ Style, Color, Modifier };
+use tui::widgets::{ Block, Borders, Paragraph, SelectableList, Text, Widget };
+
+use crate::scene::TerminalPainter;
+use crate::scene::ops::cycle_selection;
+use crate::config::tab::ThcrapConfig;
+use crate::thcrap::ThcrapInstall;
+use crate::thcrap::stack::{ PatchInfo, RunConfig };
+
+/// Builds the patch stack of a thcrap run configuration from the downloaded patches.
+pub struct PatchEditorPainter {
+
+    patches: Vec<PatchInfo>,
+    stack: Vec<String>,
+    run_config: RunConfig,
+
+    is_stack_focused: bool,
+    patch_index: Option<usize>,
+    stack_index: Option<usize>,
+    notice: String,
+
+    layout: Layout,
+    columns: Layout,
+    style_selected: Style,
+    style_unselect: Style,
+}
+
+impl TerminalPainter for PatchEditorPainter {
+
+    fn draw(&mut self, f: &mut crate::DstFrame, area: Rect) {
+
+        let chunks = self.layout.clone().split(area);
+        let columns = self.columns.clone().split(chunks[0]);
+
+        let patches: Vec<String> = self.patches.iter().map(|patch| {
+            let mark = if self.stack.contains(&patch.archive()) { "*" } else { " " };
+            format!("{} {}/{}  {}", mark, patch.repo, patch.id, patch.description)
+        }).collect();
+
+        // the top of the stack is applied last, so it overrides the patches below.
+        let stack: Vec<String> = self.stack.iter().enumerate()
+            .map(|(i, archive)| format!("{}. {}", i + 1, archive))
+            .collect();
+
+        let (patches_style, stack_style) = if self.is_stack_focused {
+            (self.style_unselect, self.style_selected)
+        } else {
+            (self.style_selected, self.style_unselect)
+        };
+
+        SelectableList::default()
+            .block(Block::default().borders(Borders::ALL).title("Patches"))
+            .items(&patches)
+            .select(if self.is_stack_focused { None } else { self.patch_index })
+            .style(self.style_unselect)
+            .highlight_style(patches_style)
+            .highlight_symbol(crate::config::manifest::HIGHLIGHT_SYMBOL)
+            .render(f, columns[0]);
+
+        let title = format!("Stack of {}", self.run_config.path.display());
+        SelectableList::default()
+            .block(Block::default().borders(Borders::ALL).title(&title))
+            .items(&stack)
+            .select(if self.is_stack_focused { self.stack_index } else { None })
+            .style(self.style_unselect)
+            .highlight_style(stack_style)
+            .highlight_symbol(crate::config::manifest::HIGHLIGHT_SYMBOL)
+            .render(f, columns[1]);
+
+        Paragraph::new([Text::raw(&self.notice)].iter())
+            .style(Style::default().fg(Color::Green))
+            .render(f, chunks[1]);
+    }
+}
+
+impl PatchEditorPainter {
+
+    pub fn open(thcrap: &ThcrapConfig) -> Result<PatchEditorPainter, String> {
+
+        let install = ThcrapInstall::new(&thcrap.dir);
+        let patches = install.patches()?;
+        let run_config = RunConfig::load(&install, &thcrap.config)?;
+        let stack = run_config.stack();
+
+        let notice = if patches.is_empty() {
+            format!("No patch was found in {}/repos, download some with the thcrap configuration tool.", thcrap.dir)
+        } else {
+            format!("{} patches available.", patches.len())
+        };
+
+        let editor = PatchEditorPainter {
+            patch_index: if patches.is_empty() { None } else { Some(0) },
+            stack_index: if stack.is_empty() { None } else { Some(0) },
+            patches, stack, run_config, notice,
+            is_stack_focused: false,
+            layout: Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(3), Constraint::Length(1)].as_ref()),
+            columns: Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref()),
+            style_selected: Style::default().fg(Color::LightGreen).modifier(Modifier::Bold),
+            style_unselect: Style::default().fg(Color::Gray),
+        };
+
+        Ok(editor)
+    }
+
+    pub fn switch_focus(&mut self) {
+        self.is_stack_focused = !self.is_stack_focused;
+    }
+
+    pub fn select(&mut self, is_next: bool) {
+
+        if self.is_stack_focused {
+            self.stack_index = cycle_selection(self.stack_index, self.stack.len(), is_next);
+        } else {
+            self.patch_index = cycle_selection(self.patch_index, self.patches.len(), is_next);
+        }
+    }
+
+    /// Push the selected patch on top of the stack, or remove the selected entry of the stack.
+    pub fn toggle(&mut self) {
+
+        if self.is_stack_focused {
+            if let Some(index) = self.stack_index {
+                let archive = self.stack.remove(index);
+                self.notice = format!("Removed {}.", archive);
+                self.stack_index = if self.stack.is_empty() { None } else { Some(index.min(self.stack.len() - 1)) };
+            }
+        } else if let Some(patch) = self.patch_index.and_then(|index| self.patches.get(index)) {
+            let archive = patch.archive();
+            if self.stack.contains(&archive) {
+                self.notice = format!("{} is already in the stack.", archive);
+            } else {
+                self.notice = format!("Added {}.", archive);
+                self.stack.push(archive);
+                self.stack_index = Some(self.stack.len() - 1);
+            }
+        }
+    }
+
+    /// Move the selected entry of the stack one step up (`is_up`) or down.
+    pub fn move_selected(&mut self, is_up: bool) {
+
+        if let (true, Some(index)) = (self.is_stack_focused, self.stack_index) {
+            let target = if is_up { index.checked_sub(1) } else { Some(index + 1).filter(|&i| i < self.stack.len()) };
+            if let Some(target) = target {
+                self.stack.swap(index, target);
+                self.stack_index = Some(target);
+            }
+        }
+    }
+
+    pub fn save(&mut self) {
+
+        self.run_config.set_stack(&self.stack);
+        self.notice = match self.run_config.save() {
+            | Ok(()) => format!("Saved {} patches to {}.", self.stack.len(), self.run_config.path.display()),
+            | Err(e) => e,
+        };
+    }
+}
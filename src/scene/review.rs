@@ -0,0 +1,89 @@
+
+use tui::layout::{ Layout, Rect, Direction, Constraint };
+use tui::style::{ Style, Color, Modifier };
+use tui::widgets::{ Block, Borders, Paragraph, SelectableList, Text, Widget };
+
+use crate::scene::TerminalPainter;
+use crate::scene::ops::cycle_selection;
+use crate::library::scan::Discovery;
+
+/// Lists the games found by a library scan, so the user picks the ones to add.
+pub struct ScanReviewPainter {
+
+    /// The discoveries, with whether they are accepted.
+    discoveries: Vec<(Discovery, bool)>,
+    title: &'static str,
+    selected: Option<usize>,
+    notice: String,
+
+    layout: Layout,
+    style_selected: Style,
+    style_unselect: Style,
+}
+
+impl TerminalPainter for ScanReviewPainter {
+
+    fn draw(&mut self, f: &mut crate::DstFrame, area: Rect) {
+
+        let chunks = self.layout.clone().split(area);
+
+        let lines: Vec<String> = self.discoveries.iter().map(|(discovery, is_accepted)| {
+            format!("[{}] {:<9} {}  ({})",
+                if *is_accepted { "x" } else { " " },
+                discovery.group.tab_name(),
+                discovery.item.name,
+                Some(&discovery.item.path).into_iter().chain(discovery.item.args.iter()).cloned().collect::<Vec<String>>().join(" "))
+        }).collect();
+
+        SelectableList::default()
+            .block(Block::default().borders(Borders::ALL).title(self.title))
+            .items(&lines)
+            .select(self.selected)
+            .style(self.style_unselect)
+            .highlight_style(self.style_selected)
+            .highlight_symbol(crate::config::manifest::HIGHLIGHT_SYMBOL)
+            .render(f, chunks[0]);
+
+        Paragraph::new([Text::raw(&self.notice)].iter())
+            .style(Style::default().fg(Color::Green))
+            .render(f, chunks[1]);
+    }
+}
+
+impl ScanReviewPainter {
+
+    pub fn new(title: &'static str, discoveries: Vec<Discovery>, notice: String) -> ScanReviewPainter {
+
+        ScanReviewPainter {
+            title,
+            selected: if discoveries.is_empty() { None } else { Some(0) },
+            discoveries: discoveries.into_iter().map(|discovery| (discovery, true)).collect(),
+            notice,
+            layout: Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(3), Constraint::Length(1)].as_ref()),
+            style_selected: Style::default().fg(Color::LightGreen).modifier(Modifier::Bold),
+            style_unselect: Style::default().fg(Color::Gray),
+        }
+    }
+
+    pub fn select(&mut self, is_next: bool) {
+        self.selected = cycle_selection(self.selected, self.discoveries.len(), is_next);
+    }
+
+    pub fn toggle(&mut self) {
+
+        if let Some(entry) = self.selected.and_then(|index| self.discoveries.get_mut(index)) {
+            entry.1 = !entry.1;
+        }
+    }
+
+    /// Close the review, returning the accepted discoveries.
+    pub fn accepted(self) -> Vec<Discovery> {
+
+        self.discoveries.into_iter()
+            .filter(|(_, is_accepted)| *is_accepted)
+            .map(|(discovery, _)| discovery)
+            .collect()
+    }
+}
@@ -0,0 +1,55 @@
+
+use serde::Serialize;
+use serde_json::ser::{ PrettyFormatter, Serializer };
+
+/// A JSON value, as found in the configuration files of thcrap. Objects keep the order of their keys,
+/// so saved files stay close to the originals.
+pub type Json = serde_json::Value;
+
+pub fn parse(text: &str) -> Result<Json, String> {
+
+    // the files written by Windows editors may start with a byte order mark.
+    serde_json::from_str(text.trim_start_matches('\u{feff}')).map_err(|e| e.to_string())
+}
+
+/// Serialize with tab indentation, like thcrap does.
+pub fn to_pretty_string(json: &Json) -> String {
+
+    let mut out = vec![];
+    let mut serializer = Serializer::with_formatter(&mut out, PrettyFormatter::with_indent(b"\t"));
+    // a `Value` always serializes, and into UTF-8.
+    json.serialize(&mut serializer).expect("Failed to serialize JSON");
+
+    let mut out = String::from_utf8(out).expect("Failed to serialize JSON");
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn keeps_the_order_of_keys_and_large_integers() {
+
+        let text = "{\n\t\"patches\": [\n\t\t{\n\t\t\t\"archive\": \"repos/thpatch/lang_en/\"\n\t\t}\n\t],\n\t\"console\": false,\n\t\"seed\": 18446744073709551615,\n\t\"scale\": 1.5,\n\t\"note\": \"\\u0001\\\"東方\\\"\",\n\t\"empty\": {}\n}\n";
+        let json = parse(text).unwrap();
+
+        assert_eq!(json["seed"].as_u64(), Some(u64::MAX));
+        assert_eq!(to_pretty_string(&json), text);
+    }
+
+    #[test]
+    fn skips_a_byte_order_mark() {
+        assert_eq!(parse("\u{feff}{\"console\": true}").unwrap()["console"], Json::Bool(true));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+
+        for text in [r#""\u12""#, r#""\ud83c\u12""#, r#""abc"#, "{\"a\" 1}", "[1, 2", "tru", "[1] 2", ""].iter() {
+            assert!(parse(text).is_err(), "{:?} should not parse", text);
+        }
+    }
+}
@@ -0,0 +1,97 @@
+
+pub mod json;
+pub mod stack;
+
+use std::fs;
+use std::path::{ Path, PathBuf };
+
+use crate::config::tab::ThcrapConfig;
+use crate::thcrap::json::Json;
+
+/// An installation of thcrap, the Touhou Community Reliant Automatic Patcher, which applies
+/// patch stacks such as translations to the games through `thcrap_loader.exe`.
+pub struct ThcrapInstall {
+
+    pub dir: PathBuf,
+}
+
+impl ThcrapInstall {
+
+    pub fn new(dir: &str) -> ThcrapInstall {
+        ThcrapInstall { dir: PathBuf::from(dir) }
+    }
+
+    /// Recent releases keep the loader in `bin/`, older ones next to the configuration.
+    pub fn loader(&self) -> Option<PathBuf> {
+
+        ["bin/thcrap_loader.exe", "thcrap_loader.exe"].iter()
+            .map(|candidate| self.dir.join(candidate))
+            .find(|path| path.is_file())
+    }
+
+    pub fn config_dir(&self) -> PathBuf {
+        self.dir.join("config")
+    }
+
+    /// The file of the run configuration `name`, in `config/` or, for older releases, at the top level.
+    pub fn run_config(&self, name: &str) -> Option<PathBuf> {
+
+        let file = run_config_file(name);
+        [self.config_dir().join(&file), self.dir.join(&file)].iter()
+            .find(|path| path.is_file())
+            .cloned()
+    }
+
+    /// The game ids found by the thcrap configuration tool, from `config/games.js`.
+    pub fn game_ids(&self) -> Result<Vec<String>, String> {
+
+        let path = self.config_dir().join("games.js");
+        let games = read_json(&path)?;
+
+        let ids = games.as_object()
+            .ok_or_else(|| format!("{} is not a JSON object", path.display()))?
+            .iter()
+            .map(|(id, _)| id.clone())
+            .collect();
+        Ok(ids)
+    }
+}
+
+/// The run configuration name with the `.js` extension thcrap expects.
+pub fn run_config_file(name: &str) -> String {
+
+    if name.ends_with(".js") {
+        name.to_owned()
+    } else {
+        format!("{}.js", name)
+    }
+}
+
+pub fn read_json(path: &Path) -> Result<Json, String> {
+
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+    json::parse(&text)
+        .map_err(|e| format!("{} is not valid JSON: {}", path.display(), e))
+}
+
+/// Check `config` against its installation and return the loader with its arguments.
+pub fn loader_command(config: &ThcrapConfig) -> Result<(PathBuf, Vec<String>), String> {
+
+    let install = ThcrapInstall::new(&config.dir);
+
+    let loader = install.loader()
+        .ok_or_else(|| format!("thcrap_loader.exe not found in {}", config.dir))?;
+
+    if install.run_config(&config.config).is_none() {
+        return Err(format!("run configuration `{}` not found in {}", config.config, install.config_dir().display()))
+    }
+
+    let ids = install.game_ids()?;
+    if !ids.iter().any(|id| id == &config.game) {
+        return Err(format!("game id `{}` is not in games.js of {}, search for the game in the thcrap configuration tool",
+            config.game, config.dir))
+    }
+
+    Ok((loader, vec![run_config_file(&config.config), config.game.clone()]))
+}
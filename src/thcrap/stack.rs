@@ -0,0 +1,226 @@
+
+use std::fs;
+use std::path::PathBuf;
+
+use crate::thcrap::{ self, ThcrapInstall };
+use crate::thcrap::json::{ self, Json };
+
+/// A patch available in the `repos/` directory of an installation.
+#[derive(Debug, Clone)]
+pub struct PatchInfo {
+
+    pub repo: String,
+    pub id: String,
+    pub description: String,
+}
+
+/// A run configuration, whose `patches` array is the patch stack applied to the game.
+pub struct RunConfig {
+
+    pub path: PathBuf,
+    /// The whole file, so the settings other than `patches` are saved back untouched.
+    json: Json,
+}
+
+impl PatchInfo {
+
+    /// The entry of this patch in the `patches` array of a run configuration.
+    pub fn archive(&self) -> String {
+        format!("repos/{}/{}/", self.repo, self.id)
+    }
+}
+
+impl ThcrapInstall {
+
+    /// List the downloaded patches, sorted by repository and id.
+    pub fn patches(&self) -> Result<Vec<PatchInfo>, String> {
+
+        let repos_dir = self.dir.join("repos");
+        let repos = fs::read_dir(&repos_dir)
+            .map_err(|e| format!("failed to read {}: {}", repos_dir.display(), e))?;
+
+        let mut patches = vec![];
+
+        for repo in repos.filter_map(Result::ok).filter(|entry| entry.path().is_dir()) {
+            let repo_name = repo.file_name().to_string_lossy().into_owned();
+
+            // `repo.js` describes every patch of the repository, even the ones not downloaded yet.
+            let descriptions = thcrap::read_json(&repo.path().join("repo.js")).ok()
+                .and_then(|repo_js| repo_js.get("patches").cloned());
+
+            let dirs = match fs::read_dir(repo.path()) {
+                | Ok(dirs) => dirs,
+                | Err(_) => continue,
+            };
+
+            for dir in dirs.filter_map(Result::ok).filter(|entry| entry.path().join("patch.js").is_file()) {
+                let id = dir.file_name().to_string_lossy().into_owned();
+
+                let description = descriptions.as_ref()
+                    .and_then(|descriptions| descriptions.get(&id))
+                    .and_then(Json::as_str)
+                    .map(str::to_owned)
+                    .or_else(|| {
+                        thcrap::read_json(&dir.path().join("patch.js")).ok()
+                            .and_then(|patch_js| patch_js.get("title").and_then(Json::as_str).map(str::to_owned))
+                    })
+                    .unwrap_or_default();
+
+                patches.push(PatchInfo { repo: repo_name.clone(), id, description });
+            }
+        }
+
+        patches.sort_by(|a, b| (&a.repo, &a.id).cmp(&(&b.repo, &b.id)));
+        Ok(patches)
+    }
+}
+
+impl RunConfig {
+
+    /// Read the run configuration `name`, or start an empty one in `config/` if it does not exist yet.
+    pub fn load(install: &ThcrapInstall, name: &str) -> Result<RunConfig, String> {
+
+        match install.run_config(name) {
+            | Some(path) => {
+                let json = thcrap::read_json(&path)?;
+                if json.as_object().is_none() {
+                    return Err(format!("{} is not a JSON object", path.display()))
+                }
+                Ok(RunConfig { path, json })
+            },
+            | None => {
+                let path = install.config_dir().join(thcrap::run_config_file(name));
+                Ok(RunConfig { path, json: serde_json::json!({ "patches": [] }) })
+            },
+        }
+    }
+
+    /// The archives of the patch stack, from the bottom to the top.
+    pub fn stack(&self) -> Vec<String> {
+
+        self.json.get("patches")
+            .and_then(Json::as_array)
+            .map(|patches| {
+                patches.iter()
+                    .filter_map(|patch| patch.get("archive").and_then(Json::as_str))
+                    .map(str::to_owned)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Reorder the patch stack to `archives`, keeping the other settings of the entries already there.
+    pub fn set_stack(&mut self, archives: &[String]) {
+
+        let mut existing = self.json.get("patches")
+            .and_then(Json::as_array)
+            .cloned()
+            .unwrap_or_default();
+
+        let mut patches: Vec<Json> = archives.iter()
+            .map(|archive| {
+                let position = existing.iter()
+                    .position(|patch| patch.get("archive").and_then(Json::as_str) == Some(archive.as_str()));
+                match position {
+                    | Some(position) => existing.remove(position),
+                    | None => serde_json::json!({ "archive": archive }),
+                }
+            })
+            .collect();
+
+        // entries without an archive are not part of the stack shown, but are not ours to drop either.
+        patches.extend(existing.into_iter().filter(|patch| patch.get("archive").and_then(Json::as_str).is_none()));
+
+        if let Some(object) = self.json.as_object_mut() {
+            object.insert(String::from("patches"), Json::Array(patches));
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("failed to create {}: {}", dir.display(), e))?;
+        }
+
+        fs::write(&self.path, json::to_pretty_string(&self.json))
+            .map_err(|e| format!("failed to write {}: {}", self.path.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::utils::testing::{ fixture, fixture_copy };
+
+    #[test]
+    fn lists_downloaded_patches() {
+
+        let install = ThcrapInstall::new(fixture("thcrap").to_str().unwrap());
+        let patches = install.patches().unwrap();
+
+        let listed: Vec<(String, String)> = patches.iter()
+            .map(|patch| (patch.archive(), patch.description.clone()))
+            .collect();
+        assert_eq!(listed, vec![
+            (String::from("repos/nmlgc/base_tsa/"), String::from("Base patch for Team Shanghai Alice games")),
+            (String::from("repos/thpatch/lang_en/"), String::from("English translation")),
+            // not in `repo.js`, so described by its own `patch.js`.
+            (String::from("repos/thpatch/lang_ja/"), String::from("Japanese (original)")),
+        ]);
+    }
+
+    #[test]
+    fn reorders_and_saves_the_stack() {
+
+        let dir = fixture_copy("thcrap", "thcrap-stack");
+        let install = ThcrapInstall::new(dir.to_str().unwrap());
+
+        let mut config = RunConfig::load(&install, "en").unwrap();
+        assert_eq!(config.stack(), vec!["repos/nmlgc/base_tsa/", "repos/thpatch/lang_en/"]);
+
+        let stack = vec![String::from("repos/thpatch/lang_en/"), String::from("repos/thpatch/lang_ja/")];
+        config.set_stack(&stack);
+        config.save().unwrap();
+
+        let saved = RunConfig::load(&install, "en").unwrap();
+        assert_eq!(saved.stack(), stack);
+
+        // the other settings of the file and of the kept entry survive.
+        assert_eq!(saved.json.get("console"), Some(&Json::Bool(false)));
+        let patches = saved.json.get("patches").and_then(Json::as_array).unwrap();
+        assert_eq!(patches[0].get("update"), Some(&Json::Bool(false)));
+        assert_eq!(patches[1].as_object().map(|patch| patch.len()), Some(1));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn keeps_an_unchanged_stack_byte_for_byte() {
+
+        let dir = fixture_copy("thcrap", "thcrap-unchanged");
+        let install = ThcrapInstall::new(dir.to_str().unwrap());
+
+        let mut config = RunConfig::load(&install, "en").unwrap();
+        let stack = config.stack();
+        config.set_stack(&stack);
+        config.save().unwrap();
+
+        let original = fs::read_to_string(fixture("thcrap/config/en.js")).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("config/en.js")).unwrap(), original);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn starts_a_missing_run_config_empty() {
+
+        let install = ThcrapInstall::new(fixture("thcrap").to_str().unwrap());
+        let config = RunConfig::load(&install, "de").unwrap();
+
+        assert!(config.stack().is_empty());
+        assert_eq!(config.path, fixture("thcrap/config/de.js"));
+    }
+}
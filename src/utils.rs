@@ -75,3 +75,46 @@ impl THLEvents {
         self.rx.recv()
     }
 }
+
+/// Helpers shared by the tests that read fixtures or need a scratch directory.
+#[cfg(test)]
+pub mod testing {
+
+    use std::fs;
+    use std::path::{ Path, PathBuf };
+
+    /// A file or directory under `tests/fixtures`.
+    pub fn fixture(path: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(path)
+    }
+
+    /// An empty directory for the test `name`, removed first if a previous run left it behind.
+    pub fn scratch_dir(name: &str) -> PathBuf {
+
+        let dir = std::env::temp_dir().join(format!("thl-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A copy of the fixture `path` in the scratch directory of `name`, for tests that write to it.
+    pub fn fixture_copy(path: &str, name: &str) -> PathBuf {
+
+        let dir = scratch_dir(name);
+        copy_dir(&fixture(path), &dir);
+        dir
+    }
+
+    fn copy_dir(from: &Path, to: &Path) {
+
+        fs::create_dir_all(to).unwrap();
+        for entry in fs::read_dir(from).unwrap().map(Result::unwrap) {
+            let target = to.join(entry.file_name());
+            if entry.path().is_dir() {
+                copy_dir(&entry.path(), &target);
+            } else {
+                fs::copy(entry.path(), target).unwrap();
+            }
+        }
+    }
+}
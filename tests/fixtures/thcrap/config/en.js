@@ -0,0 +1,13 @@
+{
+	"console": false,
+	"dat_dump": false,
+	"patches": [
+		{
+			"archive": "repos/nmlgc/base_tsa/"
+		},
+		{
+			"archive": "repos/thpatch/lang_en/",
+			"update": false
+		}
+	]
+}
@@ -0,0 +1,4 @@
+{
+	"th08": "C:/Games/th08/th08.exe",
+	"th08_custom": "C:/Games/th08/custom.exe"
+}
@@ -0,0 +1,4 @@
+{
+	"id": "base_tsa",
+	"title": "Base patch for Team Shanghai Alice games"
+}
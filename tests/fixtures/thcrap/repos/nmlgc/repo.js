@@ -0,0 +1,7 @@
+{
+	"id": "nmlgc",
+	"title": "Touhou Patch Center base patches",
+	"patches": {
+		"base_tsa": "Base patch for Team Shanghai Alice games"
+	}
+}
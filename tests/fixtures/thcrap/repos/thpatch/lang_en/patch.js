@@ -0,0 +1,4 @@
+{
+	"id": "lang_en",
+	"title": "English"
+}
@@ -0,0 +1,4 @@
+{
+	"id": "lang_ja",
+	"title": "Japanese (original)"
+}
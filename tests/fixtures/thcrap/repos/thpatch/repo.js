@@ -0,0 +1,7 @@
+{
+	"id": "thpatch",
+	"title": "Touhou Patch Center",
+	"patches": {
+		"lang_en": "English translation"
+	}
+}